@@ -0,0 +1,19 @@
+use nalgebra as na;
+
+use echo_state_network::ElasticNet;
+
+fn main() {
+    let x1 = na::DVector::from_vec(vec![1.0, 2.0, 3.0, 4.0]);
+    let d1 = na::DVector::from_vec(vec![5.0, 6.0]);
+
+    let x2 = na::DVector::from_vec(vec![2.0, 1.0, 0.0, 1.0]);
+    let d2 = na::DVector::from_vec(vec![1.0, 3.0]);
+
+    let mut lasso = ElasticNet::lasso(4, 2, 0.5);
+    lasso.set_data(&x1, &d1).unwrap();
+    lasso.set_data(&x2, &d2).unwrap();
+    let weight = lasso.fit().unwrap();
+
+    println!("{}", lasso);
+    println!("Weight:\n{}", weight);
+}
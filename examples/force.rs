@@ -0,0 +1,75 @@
+use echo_state_network::*;
+
+const TRAIN_STEP: usize = 3000;
+const TEST_STEP: usize = 500;
+const N_X: u64 = 300;
+const UPDATE_INTERVAL: usize = 2;
+
+fn main() {
+    let (train_input, train_expected_output) = sine_data_gen(0, TRAIN_STEP);
+    let (test_input, test_expected_output) = sine_data_gen(TRAIN_STEP, TEST_STEP);
+
+    let path = format!("{}/examples/graph", env!("CARGO_MANIFEST_DIR"));
+
+    let mut model = EchoStateNetwork::new(
+        1,
+        1,
+        N_X,
+        0.1,
+        1.0,
+        1.2,
+        |x| x.tanh(),
+        Some(1.0),
+        None,
+        0.2,
+        |x| x.clone_owned(),
+        |x| x.clone_owned(),
+        false,
+        ReadoutConfig::default(),
+    )
+    .unwrap();
+
+    model
+        .force_train(
+            &train_input,
+            &train_expected_output,
+            UPDATE_INTERVAL,
+            0..TRAIN_STEP,
+        )
+        .unwrap();
+
+    let estimated_output = model.estimate_batch(&test_input).unwrap();
+
+    let y_estimated = estimated_output
+        .row(0)
+        .iter()
+        .cloned()
+        .collect::<Vec<f64>>();
+    let y_expected = test_expected_output
+        .into_iter()
+        .flatten()
+        .collect::<Vec<f64>>();
+
+    println!(
+        "Mean Squared Error: {}",
+        mean_squared_error(&y_expected, &y_estimated).unwrap()
+    );
+
+    plotter::plot(
+        "FORCE",
+        (0..TEST_STEP).map(|v| v as f64).collect::<Vec<f64>>(),
+        vec![y_expected, y_estimated],
+        vec!["Expected".to_string(), "Generated".to_string()],
+        Some(&path),
+    )
+    .unwrap();
+}
+
+fn sine_data_gen(start: usize, step: usize) -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+    let input_vec = vec![vec![1.0]; step];
+    let output_vec = (start..start + step)
+        .map(|n| vec![(n as f64 * 0.05).sin()])
+        .collect::<Vec<Vec<f64>>>();
+
+    (input_vec, output_vec)
+}
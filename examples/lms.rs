@@ -0,0 +1,23 @@
+use nalgebra as na;
+
+use echo_state_network::LMS;
+
+fn main() {
+    let x1 = na::DVector::from_vec(vec![1.0, 2.0, 3.0, 4.0]);
+    let d1 = na::DVector::from_vec(vec![5.0, 6.0]);
+
+    let x2 = na::DVector::from_vec(vec![2.0, 4.0, 6.0, 8.0]);
+    let d2 = na::DVector::from_vec(vec![10.0, 11.0]);
+
+    let mut lms = LMS::normalized(4, 2, 0.5);
+
+    for _ in 0..100 {
+        lms.set_data(&x1, &d1).unwrap();
+    }
+    println!("{}", lms);
+
+    for _ in 0..100 {
+        lms.set_data(&x2, &d2).unwrap();
+    }
+    println!("{}", lms);
+}
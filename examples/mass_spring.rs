@@ -0,0 +1,54 @@
+use echo_state_network::*;
+
+const TRAIN_STEP: usize = 3000;
+const TEST_STEP: usize = 1000;
+const N_NODES: u64 = 20;
+const WASHOUT: usize = 100;
+
+fn main() {
+    let input = (0..TRAIN_STEP + TEST_STEP)
+        .map(|n| {
+            let t = n as f64 * 0.05;
+            vec![(2.11 * t).sin() * (3.73 * t).sin() * (4.33 * t).sin()]
+        })
+        .collect::<Vec<Vec<f64>>>();
+    // A nonlinear filter of the input with memory, computed by the body of the network
+    let output = (0..TRAIN_STEP + TEST_STEP)
+        .map(|n| {
+            let delayed = if n < 2 { 0.0 } else { input[n - 2][0] };
+            vec![delayed.powi(2)]
+        })
+        .collect::<Vec<Vec<f64>>>();
+
+    let mut network = MassSpringNetwork::new(
+        1,
+        N_NODES,
+        &[0, 1, 2, 3, 4],
+        &[N_NODES as usize - 2, N_NODES as usize - 1],
+        MassSpringSensor::SpringLengths,
+        MassSpringParameters::default(),
+        Some(42),
+    )
+    .unwrap();
+    let sensor_data = network.run(&input).unwrap();
+
+    let mut readout =
+        PhysicalReservoir::new_with_config(1, network.n_sensors(), ReadoutConfig::ridge(1e-9))
+            .unwrap();
+    readout
+        .offline_train(
+            &sensor_data[WASHOUT..TRAIN_STEP],
+            &output[WASHOUT..TRAIN_STEP],
+        )
+        .unwrap();
+
+    let estimated_output = readout.estimate_batch(&sensor_data[TRAIN_STEP..]).unwrap();
+    let expected_output = output[TRAIN_STEP..]
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<f64>>();
+
+    let mse = mean_squared_error(&expected_output, estimated_output.as_slice()).unwrap();
+    println!("Mean Squared Error: {:.3e}", mse);
+}
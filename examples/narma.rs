@@ -41,14 +41,17 @@ fn main() {
         |x| x.clone_owned(),
         |x| x.clone_owned(),
         false,
-        BETA,
-    );
+        ReadoutConfig::ridge(BETA),
+    )
+    .unwrap();
 
-    model.offline_train(&train_input, &train_expected_output);
+    model
+        .offline_train(&train_input, &train_expected_output)
+        .unwrap();
 
     let mut estimated_output = vec![];
     for input in test_input.iter() {
-        estimated_output.push(model.estimate(input));
+        estimated_output.push(model.estimate(input).unwrap());
     }
 
     let (l2_error, l1_error) = get_error_rate(
@@ -110,11 +113,13 @@ fn get_error_rate(
     let mse = mean_squared_error(
         &expected_output[ignore_bits..],
         &estimated_output[ignore_bits..],
-    );
+    )
+    .unwrap();
     let mae = mean_absolute_error(
         &expected_output[ignore_bits..],
         &estimated_output[ignore_bits..],
-    );
+    )
+    .unwrap();
 
     (mse, mae)
 }
@@ -0,0 +1,97 @@
+use echo_state_network::*;
+use rand::prelude::*;
+
+const TRAIN_STEP: usize = 2000;
+const TEST_STEP: usize = 500;
+const N_X: u64 = 200;
+
+const NARMA_ALPHA: f64 = 0.3;
+const NARMA_BETA: f64 = 0.05;
+const NARMA_GAMMA: f64 = 1.5;
+const NARMA_DELTA: f64 = 0.1;
+
+const NARMA_STEP: usize = 10;
+
+const RANDOM_SEED: u64 = 42;
+const TEST_RANDOM_SEED: u64 = 92;
+
+fn main() {
+    let (train_input, train_expected_output) =
+        narma_n_data_gen(TRAIN_STEP, RANDOM_SEED, NARMA_STEP);
+    let (test_input, test_expected_output) =
+        narma_n_data_gen(TEST_STEP, TEST_RANDOM_SEED, NARMA_STEP);
+
+    let esn = EchoStateNetwork::new(
+        1,
+        1,
+        N_X,
+        0.1,
+        1.0,
+        0.9,
+        |x| x.tanh(),
+        None,
+        None,
+        1.0,
+        |x| x.clone_owned(),
+        |x| x.clone_owned(),
+        false,
+        ReadoutConfig::ridge(1e-4),
+    )
+    .unwrap();
+    let ng_reservoir =
+        NGReservoir::new(1, 1, NARMA_STEP, 1, 2, ReadoutConfig::ridge(1e-4)).unwrap();
+
+    let models: Vec<(&str, Box<dyn ReservoirComputing>)> = vec![
+        ("EchoStateNetwork", Box::new(esn)),
+        ("NGReservoir", Box::new(ng_reservoir)),
+    ];
+
+    for (name, mut model) in models {
+        let start = std::time::Instant::now();
+        model
+            .offline_train(&train_input, &train_expected_output)
+            .unwrap();
+        let training_time = start.elapsed();
+
+        let estimated_output = model.estimate_batch(&test_input).unwrap();
+        let estimated_output = estimated_output.iter().cloned().collect::<Vec<f64>>();
+        let expected_output = test_expected_output
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<f64>>();
+
+        let mse = mean_squared_error(
+            &expected_output[NARMA_STEP..],
+            &estimated_output[NARMA_STEP..],
+        )
+        .unwrap();
+        println!(
+            "{}: Mean Squared Error: {:.3e}, Training time: {:?}",
+            name, mse, training_time
+        );
+    }
+}
+
+fn narma_n_data_gen(step: usize, seed: u64, n: usize) -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let input_vec = (0..step)
+        .map(|_| vec![rng.gen_range(0.0..0.5)])
+        .collect::<Vec<Vec<f64>>>();
+
+    let mut output_vec = vec![vec![0.0]; step];
+
+    for i in n..step {
+        let mut state_sum = 0.0;
+        for j in 0..n {
+            state_sum += output_vec[i - 1 - j][0];
+        }
+        output_vec[i][0] = NARMA_ALPHA * output_vec[i - 1][0]
+            + NARMA_BETA * output_vec[i - 1][0] * state_sum
+            + NARMA_GAMMA * input_vec[i - n][0] * input_vec[i - 1][0]
+            + NARMA_DELTA;
+    }
+
+    (input_vec, output_vec)
+}
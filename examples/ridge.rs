@@ -7,8 +7,8 @@ fn main() {
     let d = na::DVector::from_vec(vec![5.0, 6.0]);
 
     let mut ridge = Ridge::new(4, 2, 0.1);
-    ridge.set_data(&x, &d);
-    let weight = ridge.fit();
+    ridge.set_data(&x, &d).unwrap();
+    let weight = ridge.fit().unwrap();
 
     println!("{}", ridge);
     println!("Weight:\n{}", weight);
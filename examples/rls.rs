@@ -12,12 +12,12 @@ fn main() {
     let mut rls = RLS::new(4, 2, 1.0, 1.0);
 
     for _ in 0..100 {
-        rls.set_data(&x1, &d1);
+        rls.set_data(&x1, &d1).unwrap();
     }
     println!("{}", rls);
 
     for _ in 0..100 {
-        rls.set_data(&x2, &d2);
+        rls.set_data(&x2, &d2).unwrap();
     }
     println!("{}", rls);
 }
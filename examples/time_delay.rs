@@ -0,0 +1,44 @@
+use echo_state_network::*;
+use rand::prelude::*;
+
+const TRAIN_STEP: usize = 2000;
+const TEST_STEP: usize = 500;
+const N_NODES: u64 = 50;
+const WASHOUT: usize = 10;
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let input = (0..TRAIN_STEP + TEST_STEP)
+        .map(|_| vec![rng.gen_range(0.0..1.0)])
+        .collect::<Vec<Vec<f64>>>();
+    // A target needing both memory and nonlinearity
+    let output = (0..TRAIN_STEP + TEST_STEP)
+        .map(|n| {
+            if n < 2 {
+                vec![0.0]
+            } else {
+                vec![input[n - 1][0] * input[n - 2][0] + 0.5 * input[n][0]]
+            }
+        })
+        .collect::<Vec<Vec<f64>>>();
+
+    let mut delay_reservoir =
+        TimeDelayReservoir::new(1, N_NODES, TimeDelayParameters::default(), None).unwrap();
+    let states = delay_reservoir.run(&input).unwrap();
+
+    let mut readout =
+        PhysicalReservoir::new_with_config(1, N_NODES, ReadoutConfig::ridge(1e-6)).unwrap();
+    readout
+        .offline_train(&states[WASHOUT..TRAIN_STEP], &output[WASHOUT..TRAIN_STEP])
+        .unwrap();
+
+    let estimated_output = readout.estimate_batch(&states[TRAIN_STEP..]).unwrap();
+    let expected_output = output[TRAIN_STEP..]
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<f64>>();
+
+    let mse = mean_squared_error(&expected_output, estimated_output.as_slice()).unwrap();
+    println!("Mean Squared Error: {:.3e}", mse);
+}
@@ -32,14 +32,17 @@ fn main() {
         |x| x.clone_owned(),
         |x| x.clone_owned(),
         false,
-        BETA,
-    );
+        ReadoutConfig::ridge(BETA),
+    )
+    .unwrap();
 
-    model.offline_train(&train_input, &train_expected_output);
+    model
+        .offline_train(&train_input, &train_expected_output)
+        .unwrap();
 
     let mut estimated_output = vec![];
     for input in test_input.iter() {
-        estimated_output.push(model.estimate(input));
+        estimated_output.push(model.estimate(input).unwrap());
     }
 
     let (bits_l2_error, bits_l1_error) =
@@ -75,7 +78,8 @@ fn main() {
         &test_expected_output,
         estimated_output,
         None,
-    );
+    )
+    .unwrap();
 }
 
 fn xor_data_gen(step: usize, seed: u64) -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
@@ -115,11 +119,13 @@ fn get_bits_error_rate(
     let mse = mean_squared_error(
         &expected_output[ignore_bits..],
         &y_tested_binary[ignore_bits..],
-    );
+    )
+    .unwrap();
     let mae = mean_absolute_error(
         &expected_output[ignore_bits..],
         &y_tested_binary[ignore_bits..],
-    );
+    )
+    .unwrap();
 
     (mse, mae)
 }
@@ -135,11 +141,13 @@ fn get_error_rate(
     let mse = mean_squared_error(
         &expected_output[ignore_bits..],
         &estimated_output[ignore_bits..],
-    );
+    )
+    .unwrap();
     let mae = mean_absolute_error(
         &expected_output[ignore_bits..],
         &estimated_output[ignore_bits..],
-    );
+    )
+    .unwrap();
 
     (mse, mae)
 }
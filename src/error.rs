@@ -0,0 +1,75 @@
+/// Error type of the crate.
+#[derive(Debug, thiserror::Error)]
+pub enum EsnError {
+    /// A vector or a sample does not have the expected length.
+    #[error("Dimension mismatch: expected {expected}, found {actual}")]
+    DimensionMismatch { expected: usize, actual: usize },
+    /// A sample of a sequence does not have the expected length.
+    #[error("Sample {index} has length {actual}, expected {expected}")]
+    SampleDimensionMismatch {
+        index: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// A matrix does not have the expected shape.
+    #[error("Shape mismatch: expected {expected:?}, found {actual:?}")]
+    ShapeMismatch {
+        expected: (usize, usize),
+        actual: (usize, usize),
+    },
+    /// A component required by the operation is missing.
+    #[error("{0} is not initialized")]
+    NotInitialized(&'static str),
+    /// The regularized correlation matrix cannot be inverted.
+    #[error("The matrix is singular")]
+    SingularMatrix,
+    /// An online update produced non-finite values or lost positive definiteness, and was rejected.
+    #[error("The {0} update diverged and was rejected")]
+    Diverged(&'static str),
+    /// Two objects that must share their kind or parameters do not.
+    #[error("Incompatible {0}")]
+    Incompatible(&'static str),
+    /// An invalid argument was given.
+    #[error("Invalid parameter: {0}")]
+    InvalidParameter(String),
+    /// The operation is not supported yet.
+    #[error("{0} is not supported")]
+    Unsupported(&'static str),
+    /// Reading or writing a file failed.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// A model or an optimizer could not be serialized or deserialized.
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Result type of the crate.
+pub type Result<T, E = EsnError> = std::result::Result<T, E>;
+
+/// Check that a vector has the expected length.
+pub(crate) fn check_dimension(expected: usize, actual: usize) -> Result<()> {
+    if expected != actual {
+        return Err(EsnError::DimensionMismatch { expected, actual });
+    }
+    Ok(())
+}
+
+/// Check that every sample of a sequence has the expected length.
+pub(crate) fn check_samples<T>(samples: &[Vec<T>], expected: usize) -> Result<()> {
+    samples
+        .iter()
+        .enumerate()
+        .try_for_each(|(index, sample)| check_sample(index, sample, expected))
+}
+
+/// Check that the sample at 'index' of a sequence has the expected length.
+pub(crate) fn check_sample<T>(index: usize, sample: &[T], expected: usize) -> Result<()> {
+    if sample.len() != expected {
+        return Err(EsnError::SampleDimensionMismatch {
+            index,
+            expected,
+            actual: sample.len(),
+        });
+    }
+    Ok(())
+}
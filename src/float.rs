@@ -0,0 +1,17 @@
+use nalgebra as na;
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Floating-point type of the models and optimizers, f32 or f64.
+pub trait Float: na::RealField + Copy + Serialize + DeserializeOwned + Send + Sync {}
+
+impl<T> Float for T where T: na::RealField + Copy + Serialize + DeserializeOwned + Send + Sync {}
+
+/// Convert an f64 constant or parameter to the floating-point type of the model.
+pub(crate) fn cast<T: Float>(value: f64) -> T {
+    na::convert(value)
+}
+
+/// Convert a value of the model to f64, e.g. to change the precision of a trained model.
+pub(crate) fn to_f64<T: Float>(value: T) -> f64 {
+    na::convert_unchecked(value)
+}
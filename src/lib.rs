@@ -1,11 +1,19 @@
+mod error;
+mod float;
 mod model;
+#[cfg(feature = "ndarray")]
+mod ndarray_interop;
 mod optimizer;
 mod plot;
 mod reservoir_computing;
 mod serialize;
 mod utils;
 
+pub use error::*;
+pub use float::*;
 pub(crate) use model::*;
+#[cfg(feature = "ndarray")]
+pub use ndarray_interop::*;
 pub use optimizer::*;
 pub use plot::*;
 pub use reservoir_computing::*;
@@ -2,8 +2,10 @@ mod feedback;
 mod input;
 mod output;
 mod reservoir;
+mod spiking_reservoir;
 
 pub(crate) use feedback::*;
 pub(crate) use input::*;
 pub(crate) use output::*;
 pub(crate) use reservoir::*;
+pub(crate) use spiking_reservoir::*;
@@ -2,29 +2,48 @@ use nalgebra as na;
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::{Float, cast, to_f64};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct Feedback {
-    weight: na::DMatrix<f64>,
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub(crate) struct Feedback<T = f64> {
+    weight: na::DMatrix<T>,
 }
 
-impl Feedback {
+impl<T: Float> Feedback<T> {
     pub fn new(n_y: u64, n_x: u64, feedback_scale: f64) -> Self {
         let size = n_x * n_y;
         let elements = (0..size)
             .map(|_| thread_rng().gen_range(-feedback_scale..feedback_scale))
             .collect::<Vec<f64>>();
 
-        let weight = na::DMatrix::from_vec(n_x as usize, n_y as usize, elements);
+        let weight = na::DMatrix::from_vec(n_x as usize, n_y as usize, elements).map(cast);
 
         Feedback { weight }
     }
 
-    pub fn give_feedback(&self, y: &na::DVector<f64>) -> na::DVector<f64> {
-        self.weight.clone() * y
+    /// Return the shape of the weight, (n_x, n_y).
+    pub fn shape(&self) -> (usize, usize) {
+        self.weight.shape()
+    }
+
+    pub fn give_feedback(&self, y: &na::DVector<T>) -> na::DVector<T> {
+        &self.weight * y
+    }
+
+    /// Apply the feedback weight to outputs given one per column.
+    pub fn give_feedback_batch(&self, y: &na::DMatrix<T>) -> na::DMatrix<T> {
+        &self.weight * y
+    }
+
+    pub fn to_precision<U: Float>(&self) -> Feedback<U> {
+        Feedback {
+            weight: self.weight.map(|v| cast(to_f64(v))),
+        }
     }
 }
 
-impl std::fmt::Display for Feedback {
+impl<T: Float> std::fmt::Display for Feedback<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Feedback: {:?}", self.weight)
     }
@@ -3,12 +3,15 @@ use rand::prelude::*;
 use rand_distr::Uniform;
 use serde::{Deserialize, Serialize};
 
+use crate::{Float, cast, to_f64};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct Input {
-    weight: na::DMatrix<f64>,
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub(crate) struct Input<T = f64> {
+    weight: na::DMatrix<T>,
 }
 
-impl Input {
+impl<T: Float> Input<T> {
     pub fn new(n_u: u64, n_x: u64, input_scale: f64) -> Self {
         let size = n_u * n_x;
 
@@ -18,17 +21,37 @@ impl Input {
             .map(|_| uniform.sample(&mut thread_rng()))
             .collect::<Vec<f64>>();
 
-        let weight = na::DMatrix::from_vec(n_x as usize, n_u as usize, elements);
+        let weight = na::DMatrix::from_vec(n_x as usize, n_u as usize, elements).map(cast);
 
         Input { weight }
     }
 
-    pub fn call(&self, u: &na::DVector<f64>) -> na::DVector<f64> {
-        self.weight.clone() * u
+    pub fn from_weight(weight: na::DMatrix<T>) -> Self {
+        Input { weight }
+    }
+
+    /// Return the shape of the weight, (n_x, n_u).
+    pub fn shape(&self) -> (usize, usize) {
+        self.weight.shape()
+    }
+
+    pub fn call(&self, u: &na::DVector<T>) -> na::DVector<T> {
+        &self.weight * u
+    }
+
+    /// Apply the input weight to inputs given one per column.
+    pub fn call_batch(&self, u: &na::DMatrix<T>) -> na::DMatrix<T> {
+        &self.weight * u
+    }
+
+    pub fn to_precision<U: Float>(&self) -> Input<U> {
+        Input {
+            weight: self.weight.map(|v| cast(to_f64(v))),
+        }
     }
 }
 
-impl std::fmt::Display for Input {
+impl<T: Float> std::fmt::Display for Input<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Input weight:\n{:5.2}", self.weight)
     }
@@ -3,12 +3,20 @@ use rand::prelude::*;
 use rand_distr::Normal;
 use serde::{Deserialize, Serialize};
 
+use crate::{Float, OutputLink, cast, to_f64};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct Output {
-    weight: na::DMatrix<f64>,
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub(crate) struct Output<T = f64> {
+    weight: na::DMatrix<T>,
+    #[serde(default)]
+    link: OutputLink,
+    /// Whether the weight has been fitted by an optimizer
+    #[serde(default)]
+    is_fitted: bool,
 }
 
-impl Output {
+impl<T: Float> Output<T> {
     pub fn new(n_y: u64, n_x: u64) -> Self {
         let size = n_x * n_y;
 
@@ -17,25 +25,68 @@ impl Output {
             .map(|_| normal.sample(&mut thread_rng()))
             .collect::<Vec<f64>>();
 
-        let weight = na::DMatrix::from_vec(n_y as usize, n_x as usize, elements);
+        let weight = na::DMatrix::from_vec(n_y as usize, n_x as usize, elements).map(cast);
+
+        Output {
+            weight,
+            link: OutputLink::Identity,
+            is_fitted: false,
+        }
+    }
+
+    /// Create a readout with a zero weight, without randomness.
+    pub fn zeros(n_y: u64, n_x: u64) -> Self {
+        Output {
+            weight: na::DMatrix::zeros(n_y as usize, n_x as usize),
+            link: OutputLink::Identity,
+            is_fitted: false,
+        }
+    }
 
-        Output { weight }
+    pub fn call(&self, x: &na::DVector<T>) -> na::DVector<T> {
+        self.link.apply(&(&self.weight * x))
     }
 
-    pub fn call(&self, x: &na::DVector<f64>) -> na::DVector<f64> {
-        self.weight.clone() * x
+    /// Apply the readout to states given one per column.
+    pub fn call_batch(&self, x: &na::DMatrix<T>) -> na::DMatrix<T> {
+        let mut y = &self.weight * x;
+        if self.link != OutputLink::Identity {
+            for mut column in y.column_iter_mut() {
+                let linked = self.link.apply(&column.clone_owned());
+                column.copy_from(&linked);
+            }
+        }
+        y
     }
 
-    pub fn output_weight(&self) -> &na::DMatrix<f64> {
+    pub fn output_weight(&self) -> &na::DMatrix<T> {
         &self.weight
     }
 
-    pub fn set_weight(&mut self, weight: na::DMatrix<f64>) {
+    pub fn set_weight(&mut self, weight: na::DMatrix<T>) {
         self.weight = weight;
+        self.is_fitted = true;
+    }
+
+    /// Return whether the weight has been set by a training, rather than initialized.
+    pub fn is_fitted(&self) -> bool {
+        self.is_fitted
+    }
+
+    pub fn set_link(&mut self, link: OutputLink) {
+        self.link = link;
+    }
+
+    pub fn to_precision<U: Float>(&self) -> Output<U> {
+        Output {
+            weight: self.weight.map(|v| cast(to_f64(v))),
+            link: self.link,
+            is_fitted: self.is_fitted,
+        }
     }
 }
 
-impl std::fmt::Display for Output {
+impl<T: Float> std::fmt::Display for Output<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Output weight:\n{:5.2}", self.weight)
     }
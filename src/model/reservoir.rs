@@ -3,40 +3,58 @@ use petgraph::Graph;
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::{Float, cast, to_f64};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct Reservoir {
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub(crate) struct Reservoir<T = f64> {
     /// Reservoir weight
-    adjacency_matrix: na::DMatrix<f64>,
+    adjacency_matrix: na::DMatrix<T>,
     /// Reservoir vector
-    x_vector: na::DVector<f64>,
+    x_vector: na::DVector<T>,
     /// Activation function for the reservoir
     #[serde(skip_serializing, skip_deserializing)]
-    activation_function: Option<fn(f64) -> f64>,
+    activation_function: Option<fn(T) -> T>,
     /// Leak rate for the reservoir
-    alpha: f64,
+    alpha: T,
 }
 
-impl Reservoir {
+impl<T: Float> Reservoir<T> {
     pub fn new(
         n_x: u64,
         density: f64,
         rho: f64,
-        activation: fn(f64) -> f64,
+        activation: fn(T) -> T,
         leaking_rate: f64,
         seed: Option<u64>,
     ) -> Self {
-        let adjacency_matrix = Self::create_adjacency_matrix(n_x, density, rho, seed);
+        let adjacency_matrix = Self::create_adjacency_matrix(n_x, density, rho, seed).map(cast);
 
         Reservoir {
             adjacency_matrix,
             x_vector: na::DVector::zeros(n_x as usize),
             activation_function: Some(activation),
-            alpha: leaking_rate,
+            alpha: cast(leaking_rate),
+        }
+    }
+
+    /// Create a reservoir with a given adjacency matrix, e.g. a deterministic topology.
+    pub fn from_weight(
+        adjacency_matrix: na::DMatrix<T>,
+        activation: fn(T) -> T,
+        leaking_rate: f64,
+    ) -> Self {
+        let n_x = adjacency_matrix.nrows();
+        Reservoir {
+            adjacency_matrix,
+            x_vector: na::DVector::zeros(n_x),
+            activation_function: Some(activation),
+            alpha: cast(leaking_rate),
         }
     }
 
     /// Create an adjacency matrix for the reservoir
-    fn create_adjacency_matrix(
+    pub fn create_adjacency_matrix(
         n_x: u64,
         density: f64,
         rho: f64,
@@ -84,16 +102,51 @@ impl Reservoir {
         adjacency_matrix
     }
 
-    pub fn call(&mut self, x_in: na::DVector<f64>) -> na::DVector<f64> {
-        self.x_vector = (1.0 - self.alpha) * self.x_vector.clone()
-            + self.alpha
-                * (self.adjacency_matrix.clone() * self.x_vector.clone() + x_in)
-                    .map(self.activation_function.unwrap());
+    /// Reset the reservoir vector to zero.
+    pub fn reset(&mut self) {
+        self.x_vector.fill(T::zero());
+    }
+
+    pub fn call(&mut self, x_in: na::DVector<T>) -> na::DVector<T> {
+        self.x_vector = &self.x_vector * (T::one() - self.alpha)
+            + (&self.adjacency_matrix * &self.x_vector + x_in)
+                .map(self.activation_function.unwrap())
+                * self.alpha;
         self.x_vector.clone()
     }
+
+    /// Advance the states of several streams, one per column, without touching the reservoir vector.
+    pub fn call_batch(&self, x: &na::DMatrix<T>, x_in: &na::DMatrix<T>) -> na::DMatrix<T> {
+        x * (T::one() - self.alpha)
+            + (&self.adjacency_matrix * x + x_in).map(self.activation_function.unwrap())
+                * self.alpha
+    }
+
+    /// Take the weight and the leak rate of another reservoir, e.g. a deserialized one.
+    /// The activation function is kept and the reservoir vector is reset.
+    pub fn copy_weight_from(&mut self, other: &Reservoir<T>) {
+        self.adjacency_matrix = other.adjacency_matrix.clone();
+        self.alpha = other.alpha;
+        self.x_vector = na::DVector::zeros(other.adjacency_matrix.nrows());
+    }
+
+    pub fn n_x(&self) -> usize {
+        self.x_vector.len()
+    }
+
+    /// Convert the weights and the state to another floating-point type.
+    /// The activation function is given again, since it depends on the type.
+    pub fn to_precision<U: Float>(&self, activation: fn(U) -> U) -> Reservoir<U> {
+        Reservoir {
+            adjacency_matrix: self.adjacency_matrix.map(|v| cast(to_f64(v))),
+            x_vector: self.x_vector.map(|v| cast(to_f64(v))),
+            activation_function: Some(activation),
+            alpha: cast(to_f64(self.alpha)),
+        }
+    }
 }
 
-impl std::fmt::Display for Reservoir {
+impl<T: Float> std::fmt::Display for Reservoir<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut displayed = format!("Reservoir adjacency matrix:\n{:5.2}", self.adjacency_matrix);
         displayed.push_str(&format!("\n\nReservoir vector:\n{:5.2}", self.x_vector));
@@ -0,0 +1,91 @@
+use nalgebra as na;
+
+use crate::{Float, LifParameters, Reservoir, cast};
+
+/// Recurrent layer of leaky integrate-and-fire neurons with exponential synapses.
+/// The state read by the readout is the exponentially filtered spike count of each neuron.
+#[derive(Debug, Clone)]
+pub(crate) struct SpikingReservoir<T = f64> {
+    /// Recurrent synaptic weight
+    weight: na::DMatrix<T>,
+    /// Membrane potentials
+    membrane: na::DVector<T>,
+    /// Synaptic currents
+    current: na::DVector<T>,
+    /// Remaining refractory steps of each neuron
+    refractory: Vec<usize>,
+    /// Spikes of the last step, 0 or 1
+    spikes: na::DVector<T>,
+    /// Filtered spike counts
+    trace: na::DVector<T>,
+    membrane_rate: T,
+    synapse_decay: T,
+    trace_decay: T,
+    threshold: T,
+    reset_potential: T,
+    refractory_steps: usize,
+}
+
+impl<T: Float> SpikingReservoir<T> {
+    pub fn new(n_x: u64, density: f64, rho: f64, lif: &LifParameters, seed: Option<u64>) -> Self {
+        let weight = Reservoir::<T>::create_adjacency_matrix(n_x, density, rho, seed).map(cast);
+        let n_x = n_x as usize;
+
+        SpikingReservoir {
+            weight,
+            membrane: na::DVector::from_element(n_x, cast(lif.reset_potential)),
+            current: na::DVector::zeros(n_x),
+            refractory: vec![0; n_x],
+            spikes: na::DVector::zeros(n_x),
+            trace: na::DVector::zeros(n_x),
+            membrane_rate: cast(lif.dt / lif.membrane_time_constant),
+            synapse_decay: cast((-lif.dt / lif.synaptic_time_constant).exp()),
+            trace_decay: cast((-lif.dt / lif.readout_time_constant).exp()),
+            threshold: cast(lif.threshold),
+            reset_potential: cast(lif.reset_potential),
+            refractory_steps: (lif.refractory_period / lif.dt).round() as usize,
+        }
+    }
+
+    /// Advance the neurons by one time step 'dt' with the input current 'x_in'
+    /// and return the filtered spike counts.
+    pub fn call(&mut self, x_in: &na::DVector<T>) -> na::DVector<T> {
+        self.current = &self.current * self.synapse_decay + &self.weight * &self.spikes + x_in;
+
+        for i in 0..self.membrane.len() {
+            if self.refractory[i] > 0 {
+                self.refractory[i] -= 1;
+                self.spikes[i] = T::zero();
+                continue;
+            }
+
+            let v = self.membrane[i];
+            // The membrane relaxes towards the rest potential plus the synaptic current.
+            let v = v + (self.reset_potential - v + self.current[i]) * self.membrane_rate;
+            if v >= self.threshold {
+                self.membrane[i] = self.reset_potential;
+                self.refractory[i] = self.refractory_steps;
+                self.spikes[i] = T::one();
+            } else {
+                self.membrane[i] = v;
+                self.spikes[i] = T::zero();
+            }
+        }
+
+        self.trace = &self.trace * self.trace_decay + &self.spikes;
+        self.trace.clone()
+    }
+
+    /// Reset the neurons to their resting state.
+    pub fn reset(&mut self) {
+        self.membrane.fill(self.reset_potential);
+        self.current.fill(T::zero());
+        self.refractory.fill(0);
+        self.spikes.fill(T::zero());
+        self.trace.fill(T::zero());
+    }
+
+    pub fn n_x(&self) -> usize {
+        self.membrane.len()
+    }
+}
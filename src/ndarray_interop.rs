@@ -0,0 +1,169 @@
+//! Interoperability with `ndarray`, enabled by the `ndarray` feature.
+//! Sequences are `Array2` with one sample per row (N x n), the usual layout of `ndarray` pipelines.
+
+use nalgebra as na;
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, ShapeBuilder};
+
+use crate::{Float, ReservoirComputing, Result, check_dimension};
+
+/// View a nalgebra matrix as an `ndarray` array of the same shape, without copy.
+pub fn matrix_view<T: Float>(matrix: &na::DMatrix<T>) -> ArrayView2<'_, T> {
+    ArrayView2::from_shape(matrix.shape().f(), matrix.as_slice())
+        .expect("A nalgebra matrix is contiguous in column-major order")
+}
+
+/// Convert a nalgebra matrix into an `ndarray` array of the same shape, reusing its buffer.
+pub fn matrix_into_array<T: Float>(matrix: na::DMatrix<T>) -> Array2<T> {
+    let shape = matrix.shape();
+    let data: Vec<T> = matrix.data.into();
+    Array2::from_shape_vec(shape.f(), data)
+        .expect("A nalgebra matrix is contiguous in column-major order")
+}
+
+/// Convert an `ndarray` array into a nalgebra matrix of the same shape.
+pub fn array_to_matrix<T: Float>(array: &ArrayView2<T>) -> na::DMatrix<T> {
+    let (nrows, ncols) = array.dim();
+    match array.t().as_slice() {
+        Some(column_major) => na::DMatrix::from_column_slice(nrows, ncols, column_major),
+        None => na::DMatrix::from_fn(nrows, ncols, |i, j| array[(i, j)]),
+    }
+}
+
+/// Convert samples given one per row (N x n) into a nalgebra matrix with one sample per column (n x N).
+fn samples_to_matrix<T: Float>(samples: &ArrayView2<T>) -> na::DMatrix<T> {
+    array_to_matrix(&samples.t())
+}
+
+/// Convert a nalgebra matrix with one sample per column into samples given one per row, reusing its buffer.
+fn matrix_into_samples<T: Float>(matrix: na::DMatrix<T>) -> Array2<T> {
+    matrix_into_array(matrix).reversed_axes()
+}
+
+/// Training and estimation of the reservoir computing models with `ndarray` arrays.
+/// Implemented for every `ReservoirComputing` model.
+pub trait NdarrayReservoirComputing<T: Float = f64> {
+    /// Online training method on a sample of the readout features.
+    fn train_array(
+        &mut self,
+        teaching_input: ArrayView1<T>,
+        teaching_output: ArrayView1<T>,
+    ) -> Result<()>;
+    /// Offline training method on sequences given one sample per row.
+    fn offline_train_array(
+        &mut self,
+        teaching_input: ArrayView2<T>,
+        teaching_output: ArrayView2<T>,
+    ) -> Result<()>;
+    /// Estimate method.
+    fn estimate_array(&mut self, input: ArrayView1<T>) -> Result<Array1<T>>;
+    /// Estimate the outputs of a sequence given one sample per row (N x n_inputs),
+    /// and return them one per row (N x n_outputs).
+    fn estimate_sequence_array(&mut self, inputs: ArrayView2<T>) -> Result<Array2<T>>;
+    /// Run the model on a teaching sequence,
+    /// and return the states and the targets of the readout, one sample per row.
+    fn harvest_states_array(
+        &mut self,
+        teaching_input: ArrayView2<T>,
+        teaching_output: ArrayView2<T>,
+    ) -> Result<(Array2<T>, Array2<T>)>;
+    /// Fit the readout on states and targets given one sample per row.
+    fn fit_from_states_array(
+        &mut self,
+        states: ArrayView2<T>,
+        targets: ArrayView2<T>,
+    ) -> Result<()>;
+}
+
+impl<T: Float, M: ReservoirComputing<T> + ?Sized> NdarrayReservoirComputing<T> for M {
+    fn train_array(
+        &mut self,
+        teaching_input: ArrayView1<T>,
+        teaching_output: ArrayView1<T>,
+    ) -> Result<()> {
+        self.train(&teaching_input.to_vec(), &teaching_output.to_vec())
+    }
+
+    fn offline_train_array(
+        &mut self,
+        teaching_input: ArrayView2<T>,
+        teaching_output: ArrayView2<T>,
+    ) -> Result<()> {
+        let (states, targets) = self.harvest_states_array(teaching_input, teaching_output)?;
+        self.fit_from_states_array(states.view(), targets.view())
+    }
+
+    fn estimate_array(&mut self, input: ArrayView1<T>) -> Result<Array1<T>> {
+        Ok(Array1::from_vec(self.estimate(&input.to_vec())?))
+    }
+
+    fn estimate_sequence_array(&mut self, inputs: ArrayView2<T>) -> Result<Array2<T>> {
+        let outputs = self.estimate_sequence(&samples_to_matrix(&inputs))?;
+        Ok(matrix_into_samples(outputs))
+    }
+
+    fn harvest_states_array(
+        &mut self,
+        teaching_input: ArrayView2<T>,
+        teaching_output: ArrayView2<T>,
+    ) -> Result<(Array2<T>, Array2<T>)> {
+        check_dimension(self.n_inputs() as usize, teaching_input.ncols())?;
+        check_dimension(self.n_outputs() as usize, teaching_output.ncols())?;
+        let teaching_input = teaching_input
+            .rows()
+            .into_iter()
+            .map(|row| row.to_vec())
+            .collect::<Vec<_>>();
+        let teaching_output = teaching_output
+            .rows()
+            .into_iter()
+            .map(|row| row.to_vec())
+            .collect::<Vec<_>>();
+
+        let (states, targets) = self.harvest_states(&teaching_input, &teaching_output)?;
+        Ok((matrix_into_samples(states), matrix_into_samples(targets)))
+    }
+
+    fn fit_from_states_array(
+        &mut self,
+        states: ArrayView2<T>,
+        targets: ArrayView2<T>,
+    ) -> Result<()> {
+        self.fit_from_states(&samples_to_matrix(&states), &samples_to_matrix(&targets))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhysicalReservoir;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::array;
+
+    #[test]
+    fn test_matrix_view_is_zero_copy() {
+        let matrix = na::DMatrix::from_row_slice(2, 3, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let view = matrix_view(&matrix);
+        assert_eq!(view, array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        assert_eq!(view.as_ptr(), matrix.as_ptr());
+        assert_eq!(array_to_matrix(&view), matrix);
+    }
+
+    #[test]
+    fn test_physical_reservoir_with_arrays() {
+        let mut reservoir = PhysicalReservoir::new_with_param(1, 2, (1.0, 1.0), 0.0).unwrap();
+        reservoir
+            .offline_train_array(
+                array![[1.0, 2.0], [3.0, 4.0]].view(),
+                array![[1.5], [3.5]].view(),
+            )
+            .unwrap();
+
+        let outputs = reservoir
+            .estimate_sequence_array(array![[5.0, 6.0], [7.0, 8.0]].view())
+            .unwrap();
+        assert_eq!(outputs.dim(), (2, 1));
+        assert_approx_eq!(outputs[(0, 0)], 5.5);
+        assert_approx_eq!(outputs[(1, 0)], 7.5);
+        assert_approx_eq!(matrix_view(reservoir.readout_weight())[(0, 1)], 0.5);
+    }
+}
@@ -1,7 +1,286 @@
-mod ridge;
-mod rls;
-
-pub use ridge::*;
-pub use rls::*;
-
-// TODO: Add optimizer trait and implement it for Ridge or other optimizers
+mod elastic_net;
+mod lms;
+mod logistic_regression;
+mod readout_config;
+mod readout_features;
+mod ridge;
+mod rls;
+mod windowed_ridge;
+
+pub use elastic_net::*;
+pub use lms::*;
+pub use logistic_regression::*;
+pub use readout_config::*;
+pub use readout_features::*;
+pub use ridge::*;
+pub use rls::*;
+pub use windowed_ridge::*;
+
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::{EsnError, Float, Result, check_dimension};
+
+/// Common interface of the readout optimizers.
+pub trait Optimizer<T: Float = f64> {
+    /// Feed a pair of reservoir state 'x' and teaching output 'd' to the optimizer.
+    fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()>;
+    /// Feed a batch of reservoir states 'x' (n_x x N) and teaching outputs 'd' (n_y x N), one sample per column.
+    fn set_batch(&mut self, x: &na::DMatrix<T>, d: &na::DMatrix<T>) -> Result<()> {
+        check_dimension(x.ncols(), d.ncols())?;
+        for (x, d) in x.column_iter().zip(d.column_iter()) {
+            self.set_data(&x.clone_owned(), &d.clone_owned())?;
+        }
+        Ok(())
+    }
+    /// Return the readout weight matrix.
+    fn fit(&self) -> Result<na::DMatrix<T>>;
+    /// Return the function applied to the product of the readout weight and the state.
+    fn link(&self) -> OutputLink {
+        OutputLink::Identity
+    }
+}
+
+/// Function applied to the readout output, e.g. to turn it into probabilities.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputLink {
+    /// Linear readout
+    #[default]
+    Identity,
+    /// Binary class probability by the logistic sigmoid
+    Logistic,
+    /// Multiclass probabilities by the softmax
+    Softmax,
+}
+
+impl OutputLink {
+    pub fn apply<T: Float>(&self, z: &na::DVector<T>) -> na::DVector<T> {
+        match self {
+            OutputLink::Identity => z.clone(),
+            OutputLink::Logistic => z.map(sigmoid),
+            OutputLink::Softmax => softmax(z),
+        }
+    }
+}
+
+/// Optimizer used by the offline training of the models.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub enum OfflineOptimizer<T = f64> {
+    Ridge(Ridge<T>),
+    ElasticNet(ElasticNet<T>),
+    LogisticRegression(LogisticRegression<T>),
+    WindowedRidge(WindowedRidge<T>),
+}
+
+impl<T: Float> Optimizer<T> for OfflineOptimizer<T> {
+    fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        match self {
+            OfflineOptimizer::Ridge(ridge) => ridge.set_data(x, d),
+            OfflineOptimizer::ElasticNet(elastic_net) => elastic_net.set_data(x, d),
+            OfflineOptimizer::LogisticRegression(logistic) => logistic.set_data(x, d),
+            OfflineOptimizer::WindowedRidge(windowed) => windowed.set_data(x, d),
+        }
+    }
+
+    fn set_batch(&mut self, x: &na::DMatrix<T>, d: &na::DMatrix<T>) -> Result<()> {
+        match self {
+            OfflineOptimizer::Ridge(ridge) => ridge.set_batch(x, d),
+            OfflineOptimizer::ElasticNet(elastic_net) => elastic_net.set_batch(x, d),
+            OfflineOptimizer::LogisticRegression(logistic) => logistic.set_batch(x, d),
+            OfflineOptimizer::WindowedRidge(windowed) => windowed.set_batch(x, d),
+        }
+    }
+
+    fn fit(&self) -> Result<na::DMatrix<T>> {
+        match self {
+            OfflineOptimizer::Ridge(ridge) => ridge.fit(),
+            OfflineOptimizer::ElasticNet(elastic_net) => elastic_net.fit(),
+            OfflineOptimizer::LogisticRegression(logistic) => logistic.fit(),
+            OfflineOptimizer::WindowedRidge(windowed) => windowed.fit(),
+        }
+    }
+
+    fn link(&self) -> OutputLink {
+        match self {
+            OfflineOptimizer::LogisticRegression(logistic) => logistic.link(),
+            _ => OutputLink::Identity,
+        }
+    }
+}
+
+impl<T: Float> OfflineOptimizer<T> {
+    /// Add the accumulated data of another optimizer of the same kind and parameters.
+    pub fn merge(&mut self, other: &OfflineOptimizer<T>) -> Result<()> {
+        match (self, other) {
+            (OfflineOptimizer::Ridge(ridge), OfflineOptimizer::Ridge(other)) => ridge.merge(other),
+            (OfflineOptimizer::ElasticNet(elastic_net), OfflineOptimizer::ElasticNet(other)) => {
+                elastic_net.merge(other)
+            }
+            (
+                OfflineOptimizer::LogisticRegression(logistic),
+                OfflineOptimizer::LogisticRegression(other),
+            ) => logistic.merge(other),
+            (OfflineOptimizer::WindowedRidge(windowed), OfflineOptimizer::WindowedRidge(other)) => {
+                windowed.merge(other)
+            }
+            _ => Err(EsnError::Incompatible("offline optimizer kinds")),
+        }
+    }
+
+    /// Clear the accumulated data.
+    pub fn reset(&mut self) {
+        match self {
+            OfflineOptimizer::Ridge(ridge) => ridge.reset(),
+            OfflineOptimizer::ElasticNet(elastic_net) => elastic_net.reset(),
+            OfflineOptimizer::LogisticRegression(logistic) => logistic.reset(),
+            OfflineOptimizer::WindowedRidge(windowed) => windowed.reset(),
+        }
+    }
+
+    /// Return the number of accumulated samples.
+    pub fn n_samples(&self) -> u64 {
+        match self {
+            OfflineOptimizer::Ridge(ridge) => ridge.n_samples(),
+            OfflineOptimizer::ElasticNet(elastic_net) => elastic_net.n_samples(),
+            OfflineOptimizer::LogisticRegression(logistic) => logistic.n_samples(),
+            OfflineOptimizer::WindowedRidge(windowed) => windowed.n_samples(),
+        }
+    }
+
+    /// Return the number of input and output variables.
+    pub fn shape(&self) -> (u64, u64) {
+        match self {
+            OfflineOptimizer::Ridge(ridge) => ridge.shape(),
+            OfflineOptimizer::ElasticNet(elastic_net) => elastic_net.shape(),
+            OfflineOptimizer::LogisticRegression(logistic) => logistic.shape(),
+            OfflineOptimizer::WindowedRidge(windowed) => windowed.shape(),
+        }
+    }
+
+    /// Return a new optimizer of the same kind and parameters for 'n_x' inputs, without the accumulated data.
+    pub fn resized(&self, n_x: u64) -> Result<Self> {
+        Ok(match self {
+            OfflineOptimizer::Ridge(ridge) => ridge.resized(n_x)?.into(),
+            OfflineOptimizer::ElasticNet(elastic_net) => elastic_net.resized(n_x).into(),
+            OfflineOptimizer::LogisticRegression(logistic) => logistic.resized(n_x).into(),
+            OfflineOptimizer::WindowedRidge(windowed) => windowed.resized(n_x).into(),
+        })
+    }
+
+    /// Convert the optimizer and its accumulated data to another floating-point type.
+    pub fn to_precision<U: Float>(&self) -> OfflineOptimizer<U> {
+        match self {
+            OfflineOptimizer::Ridge(ridge) => ridge.to_precision().into(),
+            OfflineOptimizer::ElasticNet(elastic_net) => elastic_net.to_precision().into(),
+            OfflineOptimizer::LogisticRegression(logistic) => logistic.to_precision().into(),
+            OfflineOptimizer::WindowedRidge(windowed) => windowed.to_precision().into(),
+        }
+    }
+}
+
+impl<T: Float> From<Ridge<T>> for OfflineOptimizer<T> {
+    fn from(ridge: Ridge<T>) -> Self {
+        OfflineOptimizer::Ridge(ridge)
+    }
+}
+
+impl<T: Float> From<ElasticNet<T>> for OfflineOptimizer<T> {
+    fn from(elastic_net: ElasticNet<T>) -> Self {
+        OfflineOptimizer::ElasticNet(elastic_net)
+    }
+}
+
+impl<T: Float> From<LogisticRegression<T>> for OfflineOptimizer<T> {
+    fn from(logistic: LogisticRegression<T>) -> Self {
+        OfflineOptimizer::LogisticRegression(logistic)
+    }
+}
+
+impl<T: Float> From<WindowedRidge<T>> for OfflineOptimizer<T> {
+    fn from(windowed: WindowedRidge<T>) -> Self {
+        OfflineOptimizer::WindowedRidge(windowed)
+    }
+}
+
+impl<T: Float> std::fmt::Display for OfflineOptimizer<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OfflineOptimizer::Ridge(ridge) => write!(f, "{}", ridge),
+            OfflineOptimizer::ElasticNet(elastic_net) => write!(f, "{}", elastic_net),
+            OfflineOptimizer::LogisticRegression(logistic) => write!(f, "{}", logistic),
+            OfflineOptimizer::WindowedRidge(windowed) => write!(f, "{}", windowed),
+        }
+    }
+}
+
+/// Optimizer used by the online training of the models.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub enum OnlineOptimizer<T = f64> {
+    RLS(RLS<T>),
+    LMS(LMS<T>),
+}
+
+impl<T: Float> Optimizer<T> for OnlineOptimizer<T> {
+    fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        match self {
+            OnlineOptimizer::RLS(rls) => rls.set_data(x, d),
+            OnlineOptimizer::LMS(lms) => lms.set_data(x, d),
+        }
+    }
+
+    fn fit(&self) -> Result<na::DMatrix<T>> {
+        match self {
+            OnlineOptimizer::RLS(rls) => Ok(rls.fit()),
+            OnlineOptimizer::LMS(lms) => Ok(lms.fit()),
+        }
+    }
+}
+
+impl<T: Float> OnlineOptimizer<T> {
+    /// Set the weight from which the online updates continue.
+    pub fn set_weight(&mut self, weight: na::DMatrix<T>) -> Result<()> {
+        match self {
+            OnlineOptimizer::RLS(rls) => rls.set_weight(weight),
+            OnlineOptimizer::LMS(lms) => lms.set_weight(weight),
+        }
+    }
+
+    /// Return a new optimizer of the same kind and parameters for 'n_x' inputs.
+    pub fn resized(&self, n_x: u64) -> Self {
+        match self {
+            OnlineOptimizer::RLS(rls) => rls.resized(n_x).into(),
+            OnlineOptimizer::LMS(lms) => lms.resized(n_x).into(),
+        }
+    }
+
+    /// Convert the optimizer and its state to another floating-point type.
+    pub fn to_precision<U: Float>(&self) -> OnlineOptimizer<U> {
+        match self {
+            OnlineOptimizer::RLS(rls) => rls.to_precision().into(),
+            OnlineOptimizer::LMS(lms) => lms.to_precision().into(),
+        }
+    }
+}
+
+impl<T: Float> From<RLS<T>> for OnlineOptimizer<T> {
+    fn from(rls: RLS<T>) -> Self {
+        OnlineOptimizer::RLS(rls)
+    }
+}
+
+impl<T: Float> From<LMS<T>> for OnlineOptimizer<T> {
+    fn from(lms: LMS<T>) -> Self {
+        OnlineOptimizer::LMS(lms)
+    }
+}
+
+impl<T: Float> std::fmt::Display for OnlineOptimizer<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OnlineOptimizer::RLS(rls) => write!(f, "{}", rls),
+            OnlineOptimizer::LMS(lms) => write!(f, "{}", lms),
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::{EsnError, Float, Optimizer, Result, cast, check_dimension, to_f64};
+
+const DEFAULT_MAX_ITER: usize = 1000;
+const DEFAULT_TOLERANCE: f64 = 1e-6;
+
+/// Elastic-net regression model trained by coordinate descent.
+/// Minimizes `0.5 * ||D - W X||^2 + alpha * l1_ratio * |W|_1 + 0.5 * alpha * (1 - l1_ratio) * ||W||^2`.
+/// With 'l1_ratio' = 1 this is Lasso regression, with 'l1_ratio' = 0 it is equivalent to Ridge regression.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub struct ElasticNet<T = f64> {
+    alpha: T,
+    l1_ratio: T,
+    max_iter: usize,
+    tolerance: T,
+    x_xt: na::DMatrix<T>,
+    d_xt: na::DMatrix<T>,
+    /// The number of accumulated samples
+    #[serde(default)]
+    n_samples: u64,
+}
+
+impl<T: Float> ElasticNet<T> {
+    /// Create a new ElasticNet regression model.
+    /// 'n_x' is the number of input variables and 'n_y' is the number of output variables.
+    /// 'alpha' is the overall regularization parameter and 'l1_ratio' is the share of the L1 penalty in [0, 1].
+    pub fn new(n_x: u64, n_y: u64, alpha: T, l1_ratio: T) -> Result<Self> {
+        Self::new_with_param(
+            n_x,
+            n_y,
+            alpha,
+            l1_ratio,
+            DEFAULT_MAX_ITER,
+            cast(DEFAULT_TOLERANCE),
+        )
+    }
+
+    /// Create a new Lasso regression model, i.e. an ElasticNet with only the L1 penalty.
+    pub fn lasso(n_x: u64, n_y: u64, alpha: T) -> Self {
+        Self::zeros(
+            n_x,
+            n_y,
+            alpha,
+            T::one(),
+            DEFAULT_MAX_ITER,
+            cast(DEFAULT_TOLERANCE),
+        )
+    }
+
+    /// Create a new ElasticNet regression model with parameters.
+    /// 'max_iter' is the maximum number of coordinate descent sweeps.
+    /// 'tolerance' is the largest weight change of a sweep below which the descent stops.
+    pub fn new_with_param(
+        n_x: u64,
+        n_y: u64,
+        alpha: T,
+        l1_ratio: T,
+        max_iter: usize,
+        tolerance: T,
+    ) -> Result<Self> {
+        if !(l1_ratio >= T::zero() && l1_ratio <= T::one()) {
+            return Err(EsnError::InvalidParameter(
+                "l1_ratio must be in [0, 1]".to_string(),
+            ));
+        }
+
+        Ok(Self::zeros(n_x, n_y, alpha, l1_ratio, max_iter, tolerance))
+    }
+
+    fn zeros(n_x: u64, n_y: u64, alpha: T, l1_ratio: T, max_iter: usize, tolerance: T) -> Self {
+        let x_xt = na::DMatrix::zeros(n_x as usize, n_x as usize);
+        let d_xt = na::DMatrix::zeros(n_y as usize, n_x as usize);
+
+        ElasticNet {
+            alpha,
+            l1_ratio,
+            max_iter,
+            tolerance,
+            x_xt,
+            d_xt,
+            n_samples: 0,
+        }
+    }
+
+    /// Update the internal state of the ElasticNet regression model.
+    /// 'x' is the input vector (explanatory variable) and 'd' is the output vector (response variable).
+    pub fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        check_dimension(self.x_xt.ncols(), x.len())?;
+        check_dimension(self.d_xt.nrows(), d.len())?;
+
+        self.x_xt.ger(T::one(), x, x, T::one());
+        self.d_xt.ger(T::one(), d, x, T::one());
+        self.n_samples += 1;
+
+        Ok(())
+    }
+
+    /// Update the internal state of the ElasticNet regression model with a batch of samples.
+    /// 'x' is the input matrix (n_x x N) and 'd' is the output matrix (n_y x N), one sample per column.
+    pub fn set_batch(&mut self, x: &na::DMatrix<T>, d: &na::DMatrix<T>) -> Result<()> {
+        check_dimension(self.x_xt.ncols(), x.nrows())?;
+        check_dimension(self.d_xt.nrows(), d.nrows())?;
+        check_dimension(x.ncols(), d.ncols())?;
+
+        let x_t = x.transpose();
+        self.x_xt.gemm(T::one(), x, &x_t, T::one());
+        self.d_xt.gemm(T::one(), d, &x_t, T::one());
+        self.n_samples += x.ncols() as u64;
+
+        Ok(())
+    }
+
+    /// Add the accumulated statistics of another ElasticNet regression model.
+    /// Both models must have the same dimensions and regularization parameters.
+    pub fn merge(&mut self, other: &ElasticNet<T>) -> Result<()> {
+        if self.alpha != other.alpha || self.l1_ratio != other.l1_ratio {
+            return Err(EsnError::Incompatible("ElasticNet regularizations"));
+        }
+        if self.d_xt.shape() != other.d_xt.shape() {
+            return Err(EsnError::ShapeMismatch {
+                expected: self.d_xt.shape(),
+                actual: other.d_xt.shape(),
+            });
+        }
+        self.x_xt += &other.x_xt;
+        self.d_xt += &other.d_xt;
+        self.n_samples += other.n_samples;
+
+        Ok(())
+    }
+
+    /// Clear the accumulated statistics.
+    pub fn reset(&mut self) {
+        self.x_xt.fill(T::zero());
+        self.d_xt.fill(T::zero());
+        self.n_samples = 0;
+    }
+
+    /// Return the number of accumulated samples.
+    pub fn n_samples(&self) -> u64 {
+        self.n_samples
+    }
+
+    /// Return the number of input and output variables.
+    pub fn shape(&self) -> (u64, u64) {
+        (self.x_xt.ncols() as u64, self.d_xt.nrows() as u64)
+    }
+
+    /// Return a new model with the same parameters for 'n_x' inputs, without the accumulated statistics.
+    pub fn resized(&self, n_x: u64) -> Self {
+        Self::zeros(
+            n_x,
+            self.d_xt.nrows() as u64,
+            self.alpha,
+            self.l1_ratio,
+            self.max_iter,
+            self.tolerance,
+        )
+    }
+
+    /// Convert the model and its accumulated statistics to another floating-point type.
+    pub fn to_precision<U: Float>(&self) -> ElasticNet<U> {
+        ElasticNet {
+            alpha: cast(to_f64(self.alpha)),
+            l1_ratio: cast(to_f64(self.l1_ratio)),
+            max_iter: self.max_iter,
+            tolerance: cast(to_f64(self.tolerance)),
+            x_xt: self.x_xt.map(|v| cast(to_f64(v))),
+            d_xt: self.d_xt.map(|v| cast(to_f64(v))),
+            n_samples: self.n_samples,
+        }
+    }
+
+    /// Fit the ElasticNet regression model and return the weight matrix.
+    /// Each output row is solved independently by cyclic coordinate descent on the accumulated statistics.
+    pub fn fit(&self) -> Result<na::DMatrix<T>> {
+        let n_x = self.x_xt.ncols();
+        let n_y = self.d_xt.nrows();
+
+        let l1 = self.alpha * self.l1_ratio;
+        let l2 = self.alpha * (T::one() - self.l1_ratio);
+
+        let mut weight = na::DMatrix::zeros(n_y, n_x);
+
+        for i in 0..n_y {
+            let mut w = na::DVector::<T>::zeros(n_x);
+
+            for _ in 0..self.max_iter {
+                let mut max_change = T::zero();
+
+                for j in 0..n_x {
+                    let denominator = self.x_xt[(j, j)] + l2;
+                    if denominator <= T::zero() {
+                        continue;
+                    }
+
+                    let rho =
+                        self.d_xt[(i, j)] - self.x_xt.column(j).dot(&w) + self.x_xt[(j, j)] * w[j];
+                    let w_j = soft_threshold(rho, l1) / denominator;
+
+                    max_change = max_change.max((w_j - w[j]).abs());
+                    w[j] = w_j;
+                }
+
+                if max_change < self.tolerance {
+                    break;
+                }
+            }
+
+            weight.set_row(i, &w.transpose());
+        }
+
+        Ok(weight)
+    }
+}
+
+impl<T: Float> Optimizer<T> for ElasticNet<T> {
+    fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        ElasticNet::set_data(self, x, d)
+    }
+
+    fn set_batch(&mut self, x: &na::DMatrix<T>, d: &na::DMatrix<T>) -> Result<()> {
+        ElasticNet::set_batch(self, x, d)
+    }
+
+    fn fit(&self) -> Result<na::DMatrix<T>> {
+        ElasticNet::fit(self)
+    }
+}
+
+fn soft_threshold<T: Float>(value: T, threshold: T) -> T {
+    if value > threshold {
+        value - threshold
+    } else if value < -threshold {
+        value + threshold
+    } else {
+        T::zero()
+    }
+}
+
+impl<T: Float> std::fmt::Display for ElasticNet<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut displayed = format!("Alpha: {}", self.alpha);
+        displayed.push_str(&format!("\nL1 ratio: {}", self.l1_ratio));
+        displayed.push_str(&format!("\nSamples: {}", self.n_samples));
+        displayed.push_str(&format!("\nx_xt:\n{}", self.x_xt));
+        displayed.push_str(&format!("\nd_xt:\n{}", self.d_xt));
+        write!(f, "{}", displayed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_elastic_net_matches_ridge_without_l1() {
+        let samples = [
+            (vec![1.0, 2.0, 0.5], vec![1.0]),
+            (vec![0.0, 1.0, 3.0], vec![2.0]),
+            (vec![2.0, 0.0, 1.0], vec![0.5]),
+            (vec![1.0, 1.0, 1.0], vec![1.5]),
+        ];
+
+        let mut ridge: crate::Ridge = crate::Ridge::new(3, 1, 0.1);
+        let mut elastic_net = ElasticNet::new_with_param(3, 1, 0.1, 0.0, 10000, 1e-12).unwrap();
+        for (x, d) in samples.iter() {
+            let x = na::DVector::from_vec(x.clone());
+            let d = na::DVector::from_vec(d.clone());
+            ridge.set_data(&x, &d).unwrap();
+            elastic_net.set_data(&x, &d).unwrap();
+        }
+
+        let expected = ridge.fit().unwrap();
+        let actual = elastic_net.fit().unwrap();
+        for (e, a) in expected.iter().zip(actual.iter()) {
+            assert_approx_eq!(e, a, 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_lasso_drops_irrelevant_input() {
+        let mut lasso = ElasticNet::lasso(2, 1, 1.0);
+        for n in 0..50 {
+            let relevant = (n as f64 * 0.3).sin();
+            let irrelevant = if n % 2 == 0 { 0.01 } else { -0.01 };
+            let x = na::DVector::from_vec(vec![relevant, irrelevant]);
+            let d = na::DVector::from_vec(vec![2.0 * relevant]);
+            lasso.set_data(&x, &d).unwrap();
+        }
+
+        let weight = lasso.fit().unwrap();
+        assert!(weight[(0, 0)] > 1.5);
+        assert_eq!(weight[(0, 1)], 0.0);
+    }
+}
@@ -0,0 +1,189 @@
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::{EsnError, Float, Optimizer, Result, cast, check_dimension, to_f64};
+
+/// Regularization of the normalization term of the normalized LMS.
+const NLMS_EPSILON: f64 = 1e-8;
+
+/// Least Mean Squares (LMS) optimizer.
+/// Cost per update is O(n_x * n_y), so it suits large reservoirs where RLS is too expensive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub struct LMS<T = f64> {
+    /// Step size (learning rate)
+    step_size: T,
+    /// Momentum factor. 0 disables the momentum.
+    momentum: T,
+    /// Whether the step is normalized by the squared norm of the input (NLMS)
+    normalized: bool,
+    /// Weight matrix
+    weight: na::DMatrix<T>,
+    /// Accumulated update for the momentum
+    velocity: na::DMatrix<T>,
+}
+
+impl<T: Float> LMS<T> {
+    /// Create a new LMS optimizer.
+    /// 'n_x' is the number of input variables and 'n_y' is the number of output variables.
+    pub fn new(n_x: u64, n_y: u64, step_size: T) -> Self {
+        Self::zeros(n_x, n_y, step_size, T::zero(), false)
+    }
+
+    /// Create a new normalized LMS (NLMS) optimizer.
+    /// 'step_size' should be in (0, 2) for the NLMS to converge.
+    pub fn normalized(n_x: u64, n_y: u64, step_size: T) -> Self {
+        Self::zeros(n_x, n_y, step_size, T::zero(), true)
+    }
+
+    /// Create a new LMS optimizer with parameters.
+    /// 'momentum' is the momentum factor in [0, 1).
+    /// 'normalized' selects the normalized LMS.
+    pub fn new_with_param(
+        n_x: u64,
+        n_y: u64,
+        step_size: T,
+        momentum: T,
+        normalized: bool,
+    ) -> Result<Self> {
+        if !(momentum >= T::zero() && momentum < T::one()) {
+            return Err(EsnError::InvalidParameter(
+                "momentum must be in [0, 1)".to_string(),
+            ));
+        }
+
+        Ok(Self::zeros(n_x, n_y, step_size, momentum, normalized))
+    }
+
+    fn zeros(n_x: u64, n_y: u64, step_size: T, momentum: T, normalized: bool) -> Self {
+        let weight = na::DMatrix::zeros(n_y as usize, n_x as usize);
+        let velocity = na::DMatrix::zeros(n_y as usize, n_x as usize);
+
+        LMS {
+            step_size,
+            momentum,
+            normalized,
+            weight,
+            velocity,
+        }
+    }
+
+    pub fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        check_dimension(self.weight.ncols(), x.len())?;
+        check_dimension(self.weight.nrows(), d.len())?;
+
+        let error = d - &self.weight * x;
+
+        let step_size = if self.normalized {
+            self.step_size / (cast::<T>(NLMS_EPSILON) + x.norm_squared())
+        } else {
+            self.step_size
+        };
+
+        if self.momentum > T::zero() {
+            self.velocity.ger(step_size, &error, x, self.momentum);
+            self.weight += &self.velocity;
+        } else {
+            self.weight.ger(step_size, &error, x, T::one());
+        }
+
+        Ok(())
+    }
+
+    pub fn fit(&self) -> na::DMatrix<T> {
+        self.weight.clone()
+    }
+
+    /// Set the weight from which the online updates continue, e.g. an offline-trained readout.
+    /// The momentum is reset, so that the previous updates do not push the new weight.
+    pub fn set_weight(&mut self, weight: na::DMatrix<T>) -> Result<()> {
+        if weight.shape() != self.weight.shape() {
+            return Err(EsnError::ShapeMismatch {
+                expected: self.weight.shape(),
+                actual: weight.shape(),
+            });
+        }
+        self.weight = weight;
+        self.velocity.fill(T::zero());
+
+        Ok(())
+    }
+
+    /// Return a new optimizer with the same parameters for 'n_x' inputs.
+    pub fn resized(&self, n_x: u64) -> Self {
+        Self::zeros(
+            n_x,
+            self.weight.nrows() as u64,
+            self.step_size,
+            self.momentum,
+            self.normalized,
+        )
+    }
+
+    /// Convert the optimizer and its state to another floating-point type.
+    pub fn to_precision<U: Float>(&self) -> LMS<U> {
+        LMS {
+            step_size: cast(to_f64(self.step_size)),
+            momentum: cast(to_f64(self.momentum)),
+            normalized: self.normalized,
+            weight: self.weight.map(|v| cast(to_f64(v))),
+            velocity: self.velocity.map(|v| cast(to_f64(v))),
+        }
+    }
+}
+
+impl<T: Float> Optimizer<T> for LMS<T> {
+    fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        LMS::set_data(self, x, d)
+    }
+
+    fn fit(&self) -> Result<na::DMatrix<T>> {
+        Ok(LMS::fit(self))
+    }
+}
+
+impl<T: Float> std::fmt::Display for LMS<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut displayed = format!("Step size: {}", self.step_size);
+        displayed.push_str(&format!("\nMomentum: {}", self.momentum));
+        displayed.push_str(&format!("\nNormalized: {}", self.normalized));
+        displayed.push_str(&format!("\nWeight:\n{}", self.weight));
+        write!(f, "{}", displayed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_nlms_converges() {
+        let mut lms = LMS::normalized(2, 1, 0.5);
+        for n in 0..500 {
+            let x = na::DVector::from_vec(vec![(n as f64 * 0.7).sin(), (n as f64 * 0.3).cos()]);
+            let d = na::DVector::from_vec(vec![2.0 * x[0] - x[1]]);
+            lms.set_data(&x, &d).unwrap();
+        }
+
+        let weight = lms.fit();
+        assert_approx_eq!(weight[(0, 0)], 2.0, 1e-6);
+        assert_approx_eq!(weight[(0, 1)], -1.0, 1e-6);
+    }
+
+    #[test]
+    fn test_set_weight_resets_momentum() {
+        let mut lms = LMS::new_with_param(2, 1, 0.1, 0.9, false).unwrap();
+        for n in 0..10 {
+            let x = na::DVector::from_vec(vec![1.0, n as f64 * 0.1]);
+            lms.set_data(&x, &na::DVector::from_vec(vec![3.0])).unwrap();
+        }
+
+        let weight = na::DMatrix::from_row_slice(1, 2, &[1.0, -1.0]);
+        lms.set_weight(weight.clone()).unwrap();
+        // Without an error, the weight only moves by the momentum.
+        let x = na::DVector::from_vec(vec![0.5, 0.5]);
+        lms.set_data(&x, &(&weight * &x)).unwrap();
+        assert_eq!(lms.fit(), weight);
+    }
+}
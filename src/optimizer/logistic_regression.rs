@@ -0,0 +1,374 @@
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::{EsnError, Float, Optimizer, OutputLink, Result, cast, check_dimension, to_f64};
+
+const DEFAULT_MAX_ITER: usize = 100;
+const DEFAULT_TOLERANCE: f64 = 1e-8;
+/// Number of correction pairs kept by L-BFGS
+const LBFGS_MEMORY: usize = 10;
+
+/// Logistic regression readout trained with the cross-entropy loss.
+/// With 'n_y' = 1 it is a binary logistic regression fitted by iteratively reweighted least squares (IRLS).
+/// With 'n_y' > 1 it is a multiclass softmax regression fitted by L-BFGS, the teaching outputs being one-hot vectors.
+/// Unlike Ridge, the harvested states are kept until `fit` since the loss has no sufficient statistics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub struct LogisticRegression<T = f64> {
+    /// L2 regularization parameter
+    beta: T,
+    max_iter: usize,
+    tolerance: T,
+    n_x: usize,
+    n_y: usize,
+    states: Vec<na::DVector<T>>,
+    targets: Vec<na::DVector<T>>,
+}
+
+impl<T: Float> LogisticRegression<T> {
+    /// Create a new LogisticRegression model.
+    /// 'n_x' is the number of input variables and 'n_y' is the number of classes (1 for binary classification).
+    /// 'beta' is the regularization parameter.
+    pub fn new(n_x: u64, n_y: u64, beta: T) -> Self {
+        Self::new_with_param(n_x, n_y, beta, DEFAULT_MAX_ITER, cast(DEFAULT_TOLERANCE))
+    }
+
+    /// Create a new LogisticRegression model with parameters.
+    /// 'max_iter' is the maximum number of IRLS or L-BFGS iterations.
+    /// 'tolerance' is the norm of the update (IRLS) or of the gradient (L-BFGS) at which the fit stops.
+    pub fn new_with_param(n_x: u64, n_y: u64, beta: T, max_iter: usize, tolerance: T) -> Self {
+        LogisticRegression {
+            beta,
+            max_iter,
+            tolerance,
+            n_x: n_x as usize,
+            n_y: n_y as usize,
+            states: vec![],
+            targets: vec![],
+        }
+    }
+
+    /// Store a sample.
+    /// 'x' is the input vector and 'd' is the class label: 0 or 1 if binary, one-hot otherwise.
+    pub fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        check_dimension(self.n_x, x.len())?;
+        check_dimension(self.n_y, d.len())?;
+
+        self.states.push(x.clone());
+        self.targets.push(d.clone());
+
+        Ok(())
+    }
+
+    /// Add the samples stored by another LogisticRegression model.
+    /// Both models must have the same dimensions and regularization parameter.
+    pub fn merge(&mut self, other: &LogisticRegression<T>) -> Result<()> {
+        if self.beta != other.beta {
+            return Err(EsnError::Incompatible("LogisticRegression regularizations"));
+        }
+        if self.n_x != other.n_x || self.n_y != other.n_y {
+            return Err(EsnError::ShapeMismatch {
+                expected: (self.n_y, self.n_x),
+                actual: (other.n_y, other.n_x),
+            });
+        }
+        self.states.extend(other.states.iter().cloned());
+        self.targets.extend(other.targets.iter().cloned());
+
+        Ok(())
+    }
+
+    /// Clear the stored samples.
+    pub fn reset(&mut self) {
+        self.states.clear();
+        self.targets.clear();
+    }
+
+    /// Return the number of stored samples.
+    pub fn n_samples(&self) -> u64 {
+        self.states.len() as u64
+    }
+
+    /// Return the number of input variables and classes.
+    pub fn shape(&self) -> (u64, u64) {
+        (self.n_x as u64, self.n_y as u64)
+    }
+
+    /// Return a new model with the same parameters for 'n_x' inputs, without the stored samples.
+    pub fn resized(&self, n_x: u64) -> Self {
+        Self::new_with_param(
+            n_x,
+            self.n_y as u64,
+            self.beta,
+            self.max_iter,
+            self.tolerance,
+        )
+    }
+
+    /// Convert the model and its stored samples to another floating-point type.
+    pub fn to_precision<U: Float>(&self) -> LogisticRegression<U> {
+        let convert = |samples: &Vec<na::DVector<T>>| {
+            samples.iter().map(|v| v.map(|v| cast(to_f64(v)))).collect()
+        };
+        LogisticRegression {
+            beta: cast(to_f64(self.beta)),
+            max_iter: self.max_iter,
+            tolerance: cast(to_f64(self.tolerance)),
+            n_x: self.n_x,
+            n_y: self.n_y,
+            states: convert(&self.states),
+            targets: convert(&self.targets),
+        }
+    }
+
+    /// Fit the model and return the weight matrix.
+    /// The probabilities are obtained by applying `link` to the product of the weight and the state.
+    pub fn fit(&self) -> Result<na::DMatrix<T>> {
+        if self.states.is_empty() {
+            return Ok(na::DMatrix::zeros(self.n_y, self.n_x));
+        }
+
+        let x = na::DMatrix::from_columns(&self.states);
+        let d = na::DMatrix::from_columns(&self.targets);
+
+        if self.n_y == 1 {
+            let w = self.fit_irls(&x, &d.row(0).transpose())?;
+            Ok(na::DMatrix::from_row_slice(1, self.n_x, w.as_slice()))
+        } else {
+            Ok(self.fit_lbfgs(&x, &d))
+        }
+    }
+
+    /// The link function mapping the readout output to probabilities.
+    pub fn link(&self) -> OutputLink {
+        if self.n_y == 1 {
+            OutputLink::Logistic
+        } else {
+            OutputLink::Softmax
+        }
+    }
+
+    /// Binary logistic regression by Newton's method, i.e. IRLS.
+    fn fit_irls(&self, x: &na::DMatrix<T>, d: &na::DVector<T>) -> Result<na::DVector<T>> {
+        let mut w = na::DVector::zeros(self.n_x);
+
+        for _ in 0..self.max_iter {
+            let p = (x.transpose() * &w).map(sigmoid);
+            let gradient = x * (&p - d) + &w * self.beta;
+
+            let mut weighted_x = x.clone();
+            for (mut column, p) in weighted_x.column_iter_mut().zip(p.iter()) {
+                column *= *p * (T::one() - *p);
+            }
+            let hessian =
+                &weighted_x * x.transpose() + na::DMatrix::identity(self.n_x, self.n_x) * self.beta;
+
+            let step = match hessian.clone().cholesky() {
+                Some(cholesky) => cholesky.solve(&gradient),
+                None => {
+                    let inverse = hessian
+                        .pseudo_inverse(cast(1e-12))
+                        .map_err(|_| EsnError::SingularMatrix)?;
+                    inverse * &gradient
+                }
+            };
+            w -= &step;
+
+            if step.norm() < self.tolerance {
+                break;
+            }
+        }
+
+        Ok(w)
+    }
+
+    /// Multiclass softmax regression by L-BFGS.
+    fn fit_lbfgs(&self, x: &na::DMatrix<T>, d: &na::DMatrix<T>) -> na::DMatrix<T> {
+        let (n_y, n_x) = (self.n_y, self.n_x);
+
+        let loss_and_gradient = |w: &na::DVector<T>| -> (T, na::DVector<T>) {
+            let w = na::DMatrix::from_column_slice(n_y, n_x, w.as_slice());
+            let mut p = &w * x;
+            let mut loss = cast::<T>(0.5) * self.beta * w.norm_squared();
+            for (mut column, target) in p.column_iter_mut().zip(d.column_iter()) {
+                let probability = softmax(&column.clone_owned());
+                let min_probability = cast::<T>(f32::MIN_POSITIVE.into());
+                loss -= target
+                    .iter()
+                    .zip(probability.iter())
+                    .fold(T::zero(), |sum, (t, p)| {
+                        sum + *t * p.max(min_probability).ln()
+                    });
+                column.copy_from(&probability);
+            }
+            let gradient = (p - d) * x.transpose() + w * self.beta;
+            (loss, na::DVector::from_column_slice(gradient.as_slice()))
+        };
+
+        let w = lbfgs(
+            loss_and_gradient,
+            na::DVector::zeros(n_y * n_x),
+            self.max_iter,
+            self.tolerance,
+        );
+
+        na::DMatrix::from_column_slice(n_y, n_x, w.as_slice())
+    }
+}
+
+impl<T: Float> Optimizer<T> for LogisticRegression<T> {
+    fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        LogisticRegression::set_data(self, x, d)
+    }
+
+    fn fit(&self) -> Result<na::DMatrix<T>> {
+        LogisticRegression::fit(self)
+    }
+
+    fn link(&self) -> OutputLink {
+        LogisticRegression::link(self)
+    }
+}
+
+pub(crate) fn sigmoid<T: Float>(z: T) -> T {
+    T::one() / (T::one() + (-z).exp())
+}
+
+pub(crate) fn softmax<T: Float>(z: &na::DVector<T>) -> na::DVector<T> {
+    let max = z.max();
+    let exp = z.map(|v| (v - max).exp());
+    let sum = exp.sum();
+    exp / sum
+}
+
+/// Minimize a smooth function by L-BFGS with a backtracking (Armijo) line search.
+fn lbfgs<T, F>(f: F, x0: na::DVector<T>, max_iter: usize, tolerance: T) -> na::DVector<T>
+where
+    T: Float,
+    F: Fn(&na::DVector<T>) -> (T, na::DVector<T>),
+{
+    let mut x = x0;
+    let (mut value, mut gradient) = f(&x);
+    let mut history: std::collections::VecDeque<(na::DVector<T>, na::DVector<T>, T)> =
+        std::collections::VecDeque::with_capacity(LBFGS_MEMORY);
+
+    for _ in 0..max_iter {
+        if gradient.norm() < tolerance {
+            break;
+        }
+
+        // Two-loop recursion
+        let mut q = gradient.clone();
+        let mut alphas = Vec::with_capacity(history.len());
+        for (s, y, rho) in history.iter().rev() {
+            let alpha = *rho * s.dot(&q);
+            q.axpy(-alpha, y, T::one());
+            alphas.push(alpha);
+        }
+        if let Some((s, y, _)) = history.back() {
+            q *= s.dot(y) / y.dot(y);
+        }
+        for ((s, y, rho), alpha) in history.iter().zip(alphas.iter().rev()) {
+            let beta = *rho * y.dot(&q);
+            q.axpy(*alpha - beta, s, T::one());
+        }
+        let mut direction = -q;
+        if direction.dot(&gradient) >= T::zero() {
+            direction = -gradient.clone();
+            history.clear();
+        }
+
+        let slope = direction.dot(&gradient);
+        let mut step = T::one();
+        let (next_x, next_value, next_gradient) = loop {
+            let candidate = &x + &direction * step;
+            let (candidate_value, candidate_gradient) = f(&candidate);
+            if candidate_value <= value + cast::<T>(1e-4) * step * slope || step < cast(1e-12) {
+                break (candidate, candidate_value, candidate_gradient);
+            }
+            step *= cast::<T>(0.5);
+        };
+
+        let s = &next_x - &x;
+        let y = &next_gradient - &gradient;
+        let sy = s.dot(&y);
+        if sy > cast(1e-12) {
+            if history.len() == LBFGS_MEMORY {
+                history.pop_front();
+            }
+            history.push_back((s, y, T::one() / sy));
+        }
+
+        let converged = (value - next_value).abs() < tolerance * value.abs().max(T::one());
+        x = next_x;
+        value = next_value;
+        gradient = next_gradient;
+        if converged {
+            break;
+        }
+    }
+
+    x
+}
+
+impl<T: Float> std::fmt::Display for LogisticRegression<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut displayed = format!("Beta: {}", self.beta);
+        displayed.push_str(&format!("\nClasses: {}", self.n_y));
+        displayed.push_str(&format!("\nSamples: {}", self.states.len()));
+        write!(f, "{}", displayed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_logistic_regression() {
+        let mut model = LogisticRegression::new(2, 1, 0.01);
+        for n in 0..200 {
+            let feature = (n as f64 * 0.37).sin();
+            let label = if feature > 0.0 { 1.0 } else { 0.0 };
+            let x = na::DVector::from_vec(vec![feature, 1.0]);
+            model
+                .set_data(&x, &na::DVector::from_vec(vec![label]))
+                .unwrap();
+        }
+
+        let weight = model.fit().unwrap();
+        let probability = |feature: f64| {
+            let x = na::DVector::from_vec(vec![feature, 1.0]);
+            model.link().apply(&(&weight * x))[0]
+        };
+        assert!(probability(0.8) > 0.9);
+        assert!(probability(-0.8) < 0.1);
+    }
+
+    #[test]
+    fn test_softmax_regression() {
+        let centers = [(1.0, 0.0), (-1.0, 1.0), (0.0, -1.0)];
+        let mut model = LogisticRegression::new(3, 3, 0.01);
+        for n in 0..300 {
+            let class = n % 3;
+            let noise = 0.2 * (n as f64 * 1.3).sin();
+            let x = na::DVector::from_vec(vec![
+                centers[class].0 + noise,
+                centers[class].1 - noise,
+                1.0,
+            ]);
+            let mut d = na::DVector::zeros(3);
+            d[class] = 1.0;
+            model.set_data(&x, &d).unwrap();
+        }
+
+        let weight = model.fit().unwrap();
+        for (class, center) in centers.iter().enumerate() {
+            let x = na::DVector::from_vec(vec![center.0, center.1, 1.0]);
+            let probability = model.link().apply(&(&weight * x));
+            assert!((probability.sum() - 1.0).abs() < 1e-9);
+            assert_eq!(probability.argmax().0, class);
+            assert!(probability[class] > 0.8);
+        }
+    }
+}
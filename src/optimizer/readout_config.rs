@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Kind and hyperparameters of the optimizer used by the online training.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OnlineOptimizerKind {
+    /// RLS with forgetting factor 'lambda' and initial P = I / 'delta'.
+    RLS { lambda: f64, delta: f64 },
+    /// Square-root RLS with forgetting factor 'lambda' and initial P = I / 'delta'.
+    SquareRootRLS { lambda: f64, delta: f64 },
+    /// LMS, normalized or not, with optional momentum.
+    LMS {
+        step_size: f64,
+        momentum: f64,
+        normalized: bool,
+    },
+}
+
+impl OnlineOptimizerKind {
+    /// Build the optimizer for 'n_x' inputs and 'n_y' outputs.
+    pub fn build<T: Float>(&self, n_x: u64, n_y: u64) -> Result<OnlineOptimizer<T>> {
+        Ok(match *self {
+            OnlineOptimizerKind::RLS { lambda, delta } => {
+                RLS::new(n_x, n_y, cast(lambda), cast(delta)).into()
+            }
+            OnlineOptimizerKind::SquareRootRLS { lambda, delta } => {
+                RLS::square_root(n_x, n_y, cast(lambda), cast(delta)).into()
+            }
+            OnlineOptimizerKind::LMS {
+                step_size,
+                momentum,
+                normalized,
+            } => LMS::new_with_param(n_x, n_y, cast(step_size), cast(momentum), normalized)?.into(),
+        })
+    }
+}
+
+impl Default for OnlineOptimizerKind {
+    fn default() -> Self {
+        OnlineOptimizerKind::RLS {
+            lambda: 1.0,
+            delta: 1.0,
+        }
+    }
+}
+
+/// Kind and hyperparameters of the optimizer used by the offline training.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OfflineOptimizerKind {
+    /// Ridge regression with regularization parameter 'beta'.
+    Ridge { beta: f64 },
+    /// Elastic-net regression, see `ElasticNet`.
+    ElasticNet { alpha: f64, l1_ratio: f64 },
+    /// Logistic or softmax regression with regularization parameter 'beta'.
+    LogisticRegression { beta: f64 },
+    /// Ridge regression on a window of recent samples, see `WindowedRidge`.
+    WindowedRidge { beta: f64, memory: RidgeMemory },
+}
+
+impl OfflineOptimizerKind {
+    /// Build the optimizer for 'n_x' inputs and 'n_y' outputs.
+    pub fn build<T: Float>(&self, n_x: u64, n_y: u64) -> Result<OfflineOptimizer<T>> {
+        Ok(match *self {
+            OfflineOptimizerKind::Ridge { beta } => Ridge::new(n_x, n_y, cast(beta)).into(),
+            OfflineOptimizerKind::ElasticNet { alpha, l1_ratio } => {
+                ElasticNet::new(n_x, n_y, cast(alpha), cast(l1_ratio))?.into()
+            }
+            OfflineOptimizerKind::LogisticRegression { beta } => {
+                LogisticRegression::new(n_x, n_y, cast(beta)).into()
+            }
+            OfflineOptimizerKind::WindowedRidge { beta, memory } => match memory {
+                RidgeMemory::Exponential { forgetting_factor } => {
+                    WindowedRidge::exponential(n_x, n_y, cast(beta), forgetting_factor)?.into()
+                }
+                RidgeMemory::SlidingWindow { length } => {
+                    WindowedRidge::sliding_window(n_x, n_y, cast(beta), length)?.into()
+                }
+            },
+        })
+    }
+}
+
+impl Default for OfflineOptimizerKind {
+    fn default() -> Self {
+        OfflineOptimizerKind::Ridge { beta: 0.1 }
+    }
+}
+
+/// Configuration of the readout training of a model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReadoutConfig {
+    pub online: OnlineOptimizerKind,
+    pub offline: OfflineOptimizerKind,
+}
+
+impl ReadoutConfig {
+    /// Default online optimizer and Ridge regression with regularization parameter 'beta'.
+    pub fn ridge(beta: f64) -> Self {
+        ReadoutConfig {
+            offline: OfflineOptimizerKind::Ridge { beta },
+            ..Default::default()
+        }
+    }
+}
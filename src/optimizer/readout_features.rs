@@ -0,0 +1,111 @@
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::{EsnError, Float, Result};
+
+/// Feature map applied to the reservoir state before the readout and its optimizers.
+/// Nonlinear features such as squared states break the odd symmetry of tanh reservoirs,
+/// which is needed e.g. to forecast the Lorenz system.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum ReadoutFeatures {
+    /// The reservoir state as it is.
+    #[default]
+    Linear,
+    /// The reservoir state followed by the squares of its elements, [x; x²].
+    Squared,
+    /// The reservoir state with every other element squared, which keeps the size of the state.
+    SquaredOdd,
+    /// The reservoir state followed by the products x_i x_j of the given pairs of indices.
+    Products(Vec<(usize, usize)>),
+}
+
+impl ReadoutFeatures {
+    /// Return the number of features for a reservoir of 'n_x' nodes.
+    pub fn n_features(&self, n_x: u64) -> u64 {
+        match self {
+            ReadoutFeatures::Linear | ReadoutFeatures::SquaredOdd => n_x,
+            ReadoutFeatures::Squared => 2 * n_x,
+            ReadoutFeatures::Products(pairs) => n_x + pairs.len() as u64,
+        }
+    }
+
+    /// Check that the feature map can be applied to a reservoir of 'n_x' nodes.
+    pub fn validate(&self, n_x: u64) -> Result<()> {
+        if let ReadoutFeatures::Products(pairs) = self
+            && let Some((i, j)) = pairs
+                .iter()
+                .find(|(i, j)| *i as u64 >= n_x || *j as u64 >= n_x)
+        {
+            return Err(EsnError::InvalidParameter(format!(
+                "The pair ({}, {}) is out of the {} reservoir nodes",
+                i, j, n_x
+            )));
+        }
+        Ok(())
+    }
+
+    /// Map a reservoir state to the features of the readout.
+    pub fn apply<T: Float>(&self, x: &na::DVector<T>) -> na::DVector<T> {
+        match self {
+            ReadoutFeatures::Linear => x.clone(),
+            ReadoutFeatures::Squared => {
+                let n_x = x.len();
+                na::DVector::from_fn(2 * n_x, |i, _| {
+                    if i < n_x {
+                        x[i]
+                    } else {
+                        x[i - n_x] * x[i - n_x]
+                    }
+                })
+            }
+            ReadoutFeatures::SquaredOdd => {
+                na::DVector::from_fn(x.len(), |i, _| if i % 2 == 1 { x[i] * x[i] } else { x[i] })
+            }
+            ReadoutFeatures::Products(pairs) => {
+                let n_x = x.len();
+                na::DVector::from_fn(n_x + pairs.len(), |i, _| {
+                    if i < n_x {
+                        x[i]
+                    } else {
+                        let (j, k) = pairs[i - n_x];
+                        x[j] * x[k]
+                    }
+                })
+            }
+        }
+    }
+
+    /// Map reservoir states given one per column to the features of the readout.
+    pub fn apply_batch<T: Float>(&self, x: &na::DMatrix<T>) -> na::DMatrix<T> {
+        if *self == ReadoutFeatures::Linear {
+            return x.clone();
+        }
+        let n_features = self.n_features(x.nrows() as u64) as usize;
+        let mut features = na::DMatrix::zeros(n_features, x.ncols());
+        for (n, state) in x.column_iter().enumerate() {
+            features.set_column(n, &self.apply(&state.clone_owned()));
+        }
+        features
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_features() {
+        let x = na::DVector::from_vec(vec![1.0, 2.0, 3.0]);
+
+        let squared = ReadoutFeatures::Squared.apply(&x);
+        assert_eq!(squared.as_slice(), &[1.0, 2.0, 3.0, 1.0, 4.0, 9.0]);
+
+        let squared_odd = ReadoutFeatures::SquaredOdd.apply(&x);
+        assert_eq!(squared_odd.as_slice(), &[1.0, 4.0, 3.0]);
+
+        let products = ReadoutFeatures::Products(vec![(0, 2), (1, 1)]);
+        assert_eq!(products.n_features(3), 5);
+        assert_eq!(products.apply(&x).as_slice(), &[1.0, 2.0, 3.0, 3.0, 4.0]);
+        assert!(products.validate(2).is_err());
+    }
+}
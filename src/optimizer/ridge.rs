@@ -1,48 +1,398 @@
 use nalgebra as na;
 use serde::{Deserialize, Serialize};
 
+use crate::{EsnError, Float, Optimizer, Result, cast, check_dimension, to_f64};
+
+/// Regularization term of the Ridge regression.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub enum Regularization<T = f64> {
+    /// 'beta * I' shared by all outputs
+    Scalar(T),
+    /// 'beta_i * I' for the i-th output
+    PerOutput(na::DVector<T>),
+    /// Full Tikhonov matrix (n_x x n_x) added to 'x_xt', shared by all outputs
+    Tikhonov(na::DMatrix<T>),
+}
+
 /// Ridge regression model.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Ridge {
-    beta: f64,
-    x_xt: na::DMatrix<f64>,
-    d_xt: na::DMatrix<f64>,
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub struct Ridge<T = f64> {
+    #[serde(alias = "beta")]
+    regularization: Regularization<T>,
+    x_xt: na::DMatrix<T>,
+    d_xt: na::DMatrix<T>,
+    /// The number of accumulated samples
+    #[serde(default)]
+    n_samples: u64,
 }
 
-impl Ridge {
+impl<T: Float> Ridge<T> {
     /// Create a new Ridge regression model.
     /// 'n_x' is the number of input variables and 'n_y' is the number of output variables.
     /// 'beta' is the regularization parameter.
-    pub fn new(n_x: u64, n_y: u64, beta: f64) -> Self {
+    pub fn new(n_x: u64, n_y: u64, beta: T) -> Self {
+        Self::zeros(n_x, n_y, Regularization::Scalar(beta))
+    }
+
+    /// Create a new Ridge regression model with a regularization per output or a full Tikhonov matrix.
+    pub fn new_with_regularization(
+        n_x: u64,
+        n_y: u64,
+        regularization: Regularization<T>,
+    ) -> Result<Self> {
+        match &regularization {
+            Regularization::Scalar(_) => {}
+            Regularization::PerOutput(beta) => {
+                if beta.len() != n_y as usize {
+                    return Err(EsnError::InvalidParameter(
+                        "The number of regularization parameters must be n_y".to_string(),
+                    ));
+                }
+            }
+            Regularization::Tikhonov(matrix) => {
+                if matrix.shape() != (n_x as usize, n_x as usize) {
+                    return Err(EsnError::InvalidParameter(
+                        "The Tikhonov matrix must be n_x x n_x".to_string(),
+                    ));
+                }
+            }
+        }
+
+        Ok(Self::zeros(n_x, n_y, regularization))
+    }
+
+    fn zeros(n_x: u64, n_y: u64, regularization: Regularization<T>) -> Self {
         let x_xt = na::DMatrix::zeros(n_x as usize, n_x as usize);
         let d_xt = na::DMatrix::zeros(n_y as usize, n_x as usize);
 
-        Ridge { beta, x_xt, d_xt }
+        Ridge {
+            regularization,
+            x_xt,
+            d_xt,
+            n_samples: 0,
+        }
     }
 
     /// Update the internal state of the Ridge regression model.
     /// 'x' is the input vector (explanatory variable) and 'd' is the output vector (response variable).
-    pub fn set_data(&mut self, x: &na::DVector<f64>, d: &na::DVector<f64>) {
-        self.x_xt = self.x_xt.clone() + x.clone() * x.clone().transpose();
-        self.d_xt = self.d_xt.clone() + d.clone() * x.clone().transpose();
+    pub fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        self.set_data_weighted(x, d, T::one())
+    }
+
+    /// Update the internal state of the Ridge regression model with a batch of samples.
+    /// 'x' is the input matrix (n_x x N) and 'd' is the output matrix (n_y x N), one sample per column.
+    pub fn set_batch(&mut self, x: &na::DMatrix<T>, d: &na::DMatrix<T>) -> Result<()> {
+        self.check_batch(x, d)?;
+
+        let x_t = x.transpose();
+        self.x_xt.gemm(T::one(), x, &x_t, T::one());
+        self.d_xt.gemm(T::one(), d, &x_t, T::one());
+        self.n_samples += x.ncols() as u64;
+
+        Ok(())
+    }
+
+    /// Update the internal state with a sample weighted by 'weight', i.e. weighted least squares.
+    pub fn set_data_weighted(
+        &mut self,
+        x: &na::DVector<T>,
+        d: &na::DVector<T>,
+        weight: T,
+    ) -> Result<()> {
+        check_dimension(self.x_xt.ncols(), x.len())?;
+        check_dimension(self.d_xt.nrows(), d.len())?;
+
+        self.x_xt.ger(weight, x, x, T::one());
+        self.d_xt.ger(weight, d, x, T::one());
+        self.n_samples += 1;
+
+        Ok(())
+    }
+
+    /// Update the internal state with a batch of samples, the n-th sample being weighted by `weights[n]`.
+    pub fn set_batch_weighted(
+        &mut self,
+        x: &na::DMatrix<T>,
+        d: &na::DMatrix<T>,
+        weights: &na::DVector<T>,
+    ) -> Result<()> {
+        self.check_batch(x, d)?;
+        check_dimension(x.ncols(), weights.len())?;
+
+        let mut x_w = x.clone();
+        for (mut column, weight) in x_w.column_iter_mut().zip(weights.iter()) {
+            column *= *weight;
+        }
+        let x_w_t = x_w.transpose();
+        self.x_xt.gemm(T::one(), x, &x_w_t, T::one());
+        self.d_xt.gemm(T::one(), d, &x_w_t, T::one());
+        self.n_samples += x.ncols() as u64;
+
+        Ok(())
+    }
+
+    /// Add the accumulated statistics of another Ridge regression model,
+    /// e.g. one filled by another thread on other sequences.
+    /// Both models must have the same dimensions and regularization parameter.
+    pub fn merge(&mut self, other: &Ridge<T>) -> Result<()> {
+        if self.regularization != other.regularization {
+            return Err(EsnError::Incompatible("Ridge regularizations"));
+        }
+        if self.d_xt.shape() != other.d_xt.shape() {
+            return Err(EsnError::ShapeMismatch {
+                expected: self.d_xt.shape(),
+                actual: other.d_xt.shape(),
+            });
+        }
+        self.x_xt += &other.x_xt;
+        self.d_xt += &other.d_xt;
+        self.n_samples += other.n_samples;
+
+        Ok(())
+    }
+
+    /// Clear the accumulated statistics.
+    pub fn reset(&mut self) {
+        self.x_xt.fill(T::zero());
+        self.d_xt.fill(T::zero());
+        self.n_samples = 0;
+    }
+
+    /// Return the number of accumulated samples.
+    pub fn n_samples(&self) -> u64 {
+        self.n_samples
+    }
+
+    /// Return the number of input and output variables.
+    pub fn shape(&self) -> (u64, u64) {
+        (self.x_xt.ncols() as u64, self.d_xt.nrows() as u64)
+    }
+
+    /// Return a new model with the same regularization for 'n_x' inputs, without the accumulated statistics.
+    /// A Tikhonov matrix cannot be resized.
+    pub fn resized(&self, n_x: u64) -> Result<Self> {
+        if let Regularization::Tikhonov(_) = self.regularization {
+            return Err(EsnError::Unsupported("resizing a Tikhonov regularization"));
+        }
+        Ok(Self::zeros(
+            n_x,
+            self.d_xt.nrows() as u64,
+            self.regularization.clone(),
+        ))
+    }
+
+    /// Convert the model and its accumulated statistics to another floating-point type.
+    pub fn to_precision<U: Float>(&self) -> Ridge<U> {
+        let regularization = match &self.regularization {
+            Regularization::Scalar(beta) => Regularization::Scalar(cast(to_f64(*beta))),
+            Regularization::PerOutput(beta) => {
+                Regularization::PerOutput(beta.map(|v| cast(to_f64(v))))
+            }
+            Regularization::Tikhonov(matrix) => {
+                Regularization::Tikhonov(matrix.map(|v| cast(to_f64(v))))
+            }
+        };
+        Ridge {
+            regularization,
+            x_xt: self.x_xt.map(|v| cast(to_f64(v))),
+            d_xt: self.d_xt.map(|v| cast(to_f64(v))),
+            n_samples: self.n_samples,
+        }
     }
 
     /// Fit the Ridge regression model and return the weight matrix.
-    pub fn fit(&self) -> na::DMatrix<f64> {
+    pub fn fit(&self) -> Result<na::DMatrix<T>> {
         let n_x = self.x_xt.ncols();
-        let x_xt_inv = (self.x_xt.clone() + self.beta * na::DMatrix::identity(n_x, n_x))
-            .try_inverse()
-            .unwrap();
+        match &self.regularization {
+            Regularization::Scalar(beta) => {
+                let x_xt_inv = (self.x_xt.clone() + na::DMatrix::identity(n_x, n_x) * *beta)
+                    .try_inverse()
+                    .ok_or(EsnError::SingularMatrix)?;
+
+                Ok(self.d_xt.clone() * x_xt_inv)
+            }
+            Regularization::PerOutput(beta) => {
+                let mut weight = na::DMatrix::zeros(self.d_xt.nrows(), n_x);
+                for (i, beta) in beta.iter().enumerate() {
+                    let x_xt_inv = (self.x_xt.clone() + na::DMatrix::identity(n_x, n_x) * *beta)
+                        .try_inverse()
+                        .ok_or(EsnError::SingularMatrix)?;
+                    weight.set_row(i, &(self.d_xt.row(i) * x_xt_inv));
+                }
+
+                Ok(weight)
+            }
+            Regularization::Tikhonov(matrix) => {
+                let x_xt_inv = (self.x_xt.clone() + matrix)
+                    .try_inverse()
+                    .ok_or(EsnError::SingularMatrix)?;
+
+                Ok(self.d_xt.clone() * x_xt_inv)
+            }
+        }
+    }
 
-        self.d_xt.clone() * x_xt_inv
+    fn check_batch(&self, x: &na::DMatrix<T>, d: &na::DMatrix<T>) -> Result<()> {
+        check_dimension(self.x_xt.ncols(), x.nrows())?;
+        check_dimension(self.d_xt.nrows(), d.nrows())?;
+        check_dimension(x.ncols(), d.ncols())
     }
 }
 
-impl std::fmt::Display for Ridge {
+impl<T: Float> std::fmt::Display for Ridge<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let mut displayed = format!("Beta: {}", self.beta);
+        let mut displayed = match &self.regularization {
+            Regularization::Scalar(beta) => format!("Beta: {}", beta),
+            Regularization::PerOutput(beta) => format!("Beta: {}", beta.transpose()),
+            Regularization::Tikhonov(matrix) => format!("Tikhonov matrix:\n{}", matrix),
+        };
+        displayed.push_str(&format!("\nSamples: {}", self.n_samples));
         displayed.push_str(&format!("\nx_xt:\n{}", self.x_xt));
         displayed.push_str(&format!("\nd_xt:\n{}", self.d_xt));
         write!(f, "{}", displayed)
     }
 }
+
+impl<T: Float> Optimizer<T> for Ridge<T> {
+    fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        Ridge::set_data(self, x, d)
+    }
+
+    fn set_batch(&mut self, x: &na::DMatrix<T>, d: &na::DMatrix<T>) -> Result<()> {
+        Ridge::set_batch(self, x, d)
+    }
+
+    fn fit(&self) -> Result<na::DMatrix<T>> {
+        Ridge::fit(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_batch_matches_samples() {
+        let x = na::DMatrix::from_column_slice(2, 3, &[1.0, 2.0, 0.5, -1.0, 3.0, 0.0]);
+        let d = na::DMatrix::from_column_slice(1, 3, &[1.0, 0.0, 2.0]);
+
+        let mut ridge: Ridge = Ridge::new(2, 1, 0.1);
+        for (x, d) in x.column_iter().zip(d.column_iter()) {
+            ridge.set_data(&x.clone_owned(), &d.clone_owned()).unwrap();
+        }
+        let mut batched = Ridge::new(2, 1, 0.1);
+        batched.set_batch(&x, &d).unwrap();
+
+        for (e, a) in ridge
+            .fit()
+            .unwrap()
+            .iter()
+            .zip(batched.fit().unwrap().iter())
+        {
+            assert_approx_eq!(e, a);
+        }
+    }
+
+    #[test]
+    fn test_merge_matches_single_accumulator() {
+        let x = na::DMatrix::from_column_slice(2, 4, &[1.0, 2.0, 0.5, -1.0, 3.0, 0.0, 1.0, 1.0]);
+        let d = na::DMatrix::from_column_slice(1, 4, &[1.0, 0.0, 2.0, 1.5]);
+
+        let mut single: Ridge = Ridge::new(2, 1, 0.1);
+        single.set_batch(&x, &d).unwrap();
+
+        let mut first = Ridge::new(2, 1, 0.1);
+        first
+            .set_batch(
+                &x.columns(0, 2).clone_owned(),
+                &d.columns(0, 2).clone_owned(),
+            )
+            .unwrap();
+        let mut second = Ridge::new(2, 1, 0.1);
+        second
+            .set_batch(
+                &x.columns(2, 2).clone_owned(),
+                &d.columns(2, 2).clone_owned(),
+            )
+            .unwrap();
+        first.merge(&second).unwrap();
+
+        for (e, a) in single
+            .fit()
+            .unwrap()
+            .iter()
+            .zip(first.fit().unwrap().iter())
+        {
+            assert_approx_eq!(e, a);
+        }
+    }
+
+    #[test]
+    fn test_per_output_regularization() {
+        let x = na::DMatrix::from_column_slice(2, 3, &[1.0, 2.0, 0.5, -1.0, 3.0, 0.0]);
+        let d = na::DMatrix::from_column_slice(2, 3, &[1.0, 2.0, 0.0, 1.0, 2.0, -1.0]);
+
+        let regularization = Regularization::PerOutput(na::DVector::from_vec(vec![0.1, 10.0]));
+        let mut ridge = Ridge::new_with_regularization(2, 2, regularization).unwrap();
+        ridge.set_batch(&x, &d).unwrap();
+        let weight = ridge.fit().unwrap();
+
+        for (row, beta) in [0.1, 10.0].iter().enumerate() {
+            let mut single: Ridge = Ridge::new(2, 1, *beta);
+            single.set_batch(&x, &d.rows(row, 1).clone_owned()).unwrap();
+            for (e, a) in single.fit().unwrap().iter().zip(weight.row(row).iter()) {
+                assert_approx_eq!(e, a);
+            }
+        }
+    }
+
+    #[test]
+    fn test_weighted_samples() {
+        let x = na::DMatrix::from_column_slice(2, 3, &[1.0, 2.0, 0.5, -1.0, 3.0, 0.0]);
+        let d = na::DMatrix::from_column_slice(1, 3, &[1.0, 0.0, 2.0]);
+
+        let mut weighted: Ridge = Ridge::new(2, 1, 0.1);
+        weighted
+            .set_batch_weighted(&x, &d, &na::DVector::from_vec(vec![2.0, 1.0, 0.5]))
+            .unwrap();
+
+        let mut repeated = Ridge::new(2, 1, 0.1);
+        repeated
+            .set_data_weighted(&x.column(0).clone_owned(), &d.column(0).clone_owned(), 2.0)
+            .unwrap();
+        repeated
+            .set_data(&x.column(1).clone_owned(), &d.column(1).clone_owned())
+            .unwrap();
+        repeated
+            .set_data_weighted(&x.column(2).clone_owned(), &d.column(2).clone_owned(), 0.5)
+            .unwrap();
+
+        for (e, a) in repeated
+            .fit()
+            .unwrap()
+            .iter()
+            .zip(weighted.fit().unwrap().iter())
+        {
+            assert_approx_eq!(e, a);
+        }
+    }
+
+    #[test]
+    fn test_singular_matrix_is_an_error() {
+        let ridge = Ridge::new(2, 1, 0.0);
+        assert!(matches!(ridge.fit(), Err(EsnError::SingularMatrix)));
+    }
+
+    #[test]
+    fn test_deserialize_scalar_beta() {
+        let ridge = Ridge::new(2, 1, 0.5);
+        let json = serde_json::to_string(&ridge).unwrap();
+        let json = json.replace("\"regularization\"", "\"beta\"");
+        let ridge: Ridge = serde_json::from_str(&json).unwrap();
+        assert_eq!(ridge.regularization, Regularization::Scalar(0.5));
+    }
+}
@@ -1,53 +1,311 @@
 use nalgebra as na;
 use serde::{Deserialize, Serialize};
 
+use crate::{EsnError, Float, Optimizer, Result, cast, check_dimension, to_f64};
+
+const DEFAULT_SYMMETRIZE_INTERVAL: u64 = 1;
+
+/// Form of the covariance update of the RLS optimizer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RLSForm {
+    /// Textbook update of P.
+    #[default]
+    Standard,
+    /// Square-root (Potter) update of a factor S with P = S S^T.
+    /// P stays symmetric positive semi-definite by construction, at the same O(n_x^2) cost.
+    SquareRoot,
+}
+
 /// Recursive Least Squares (RLS) optimizer.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RLS {
-    /// Auxiliary variable
-    p: na::DMatrix<f64>,
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub struct RLS<T = f64> {
+    /// Auxiliary variable. In the square-root form, its factor S with P = S S^T.
+    p: na::DMatrix<T>,
     /// Forgetting factor
-    lambda: f64,
+    lambda: T,
+    /// Regularization, P being initialized to I / alpha
+    #[serde(default = "T::one")]
+    alpha: T,
     /// Weight matrix
-    weight: na::DMatrix<f64>,
+    weight: na::DMatrix<T>,
+    /// Form of the update of P
+    #[serde(default)]
+    form: RLSForm,
+    /// The number of updates between two re-symmetrizations of P in the standard form. 0 disables it.
+    #[serde(default)]
+    symmetrize_interval: u64,
+    /// The number of accepted updates
+    #[serde(default)]
+    steps: u64,
+    /// Whether an update has been rejected because it diverged
+    #[serde(default)]
+    diverged: bool,
 }
 
-impl RLS {
-    pub fn new(n_x: u64, n_y: u64, lambda: f64, alpha: f64) -> Self {
+impl<T: Float> RLS<T> {
+    pub fn new(n_x: u64, n_y: u64, lambda: T, alpha: T) -> Self {
+        Self::new_with_param(
+            n_x,
+            n_y,
+            lambda,
+            alpha,
+            RLSForm::Standard,
+            DEFAULT_SYMMETRIZE_INTERVAL,
+        )
+    }
+
+    /// Create a new RLS optimizer with the square-root form of the update.
+    pub fn square_root(n_x: u64, n_y: u64, lambda: T, alpha: T) -> Self {
+        Self::new_with_param(n_x, n_y, lambda, alpha, RLSForm::SquareRoot, 0)
+    }
+
+    /// Create a new RLS optimizer with parameters.
+    /// 'lambda' is the forgetting factor and 'alpha' the regularization, P being initialized to I / alpha.
+    /// 'symmetrize_interval' is the number of updates between two re-symmetrizations of P (standard form only).
+    pub fn new_with_param(
+        n_x: u64,
+        n_y: u64,
+        lambda: T,
+        alpha: T,
+        form: RLSForm,
+        symmetrize_interval: u64,
+    ) -> Self {
         let mut p = na::DMatrix::identity(n_x as usize, n_x as usize);
-        p *= 1.0 / alpha;
+        p *= match form {
+            RLSForm::Standard => T::one() / alpha,
+            RLSForm::SquareRoot => T::one() / alpha.sqrt(),
+        };
 
         let weight = na::DMatrix::zeros(n_y as usize, n_x as usize);
 
-        RLS { p, lambda, weight }
+        RLS {
+            p,
+            lambda,
+            alpha,
+            weight,
+            form,
+            symmetrize_interval,
+            steps: 0,
+            diverged: false,
+        }
     }
 
-    pub fn set_data(&mut self, x: &na::DVector<f64>, d: &na::DVector<f64>) {
-        let p = self.p.clone();
+    /// Update the weight with a sample.
+    /// An update producing non-finite values or an indefinite P is rejected:
+    /// the previous state is kept, a warning is logged, `is_diverged` turns true and an error is returned.
+    pub fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        check_dimension(self.weight.ncols(), x.len())?;
+        check_dimension(self.weight.nrows(), d.len())?;
+
+        let (gain, p) = match self.form {
+            RLSForm::Standard => self.standard_update(x),
+            RLSForm::SquareRoot => self.square_root_update(x),
+        };
 
-        let p1 = p.clone() / self.lambda;
-        let p2 = p.clone() * x.clone() * x.clone().transpose() * p.clone().transpose();
-        let p3 = self.lambda + (x.clone().transpose() * p.clone() * x.clone())[(0, 0)];
+        let y = &self.weight * x;
+        let weight = &self.weight + (d - y) * gain.transpose();
 
-        self.p = p1 - p2 / p3;
+        let is_finite = weight.iter().chain(p.iter()).all(|v| v.is_finite());
+        let is_definite =
+            self.form == RLSForm::SquareRoot || p.diagonal().iter().all(|v| *v > T::zero());
+        if !is_finite || !is_definite {
+            if !self.diverged {
+                log::warn!("RLS update diverged and was rejected (step {})", self.steps);
+            }
+            self.diverged = true;
+            return Err(EsnError::Diverged("RLS"));
+        }
 
-        let y = self.weight.clone() * x.clone();
-        let w1 = self.weight.clone();
-        let w2 = (1. / self.lambda) * (d.clone() - y.clone()) * (p * x.clone()).transpose();
+        self.weight = weight;
+        self.p = p;
+        self.steps += 1;
 
-        self.weight = w1 + w2;
+        if self.form == RLSForm::Standard
+            && self.symmetrize_interval > 0
+            && self.steps.is_multiple_of(self.symmetrize_interval)
+        {
+            self.p = (&self.p + self.p.transpose()) * cast::<T>(0.5);
+        }
+
+        Ok(())
     }
 
-    pub fn fit(&self) -> na::DMatrix<f64> {
+    pub fn fit(&self) -> na::DMatrix<T> {
         self.weight.clone()
     }
+
+    /// Set the weight from which the online updates continue, e.g. an offline-trained readout.
+    pub fn set_weight(&mut self, weight: na::DMatrix<T>) -> Result<()> {
+        if weight.shape() != self.weight.shape() {
+            return Err(EsnError::ShapeMismatch {
+                expected: self.weight.shape(),
+                actual: weight.shape(),
+            });
+        }
+        self.weight = weight;
+
+        Ok(())
+    }
+
+    /// Whether an update has been rejected because it diverged.
+    pub fn is_diverged(&self) -> bool {
+        self.diverged
+    }
+
+    /// Return a new optimizer with the same parameters for 'n_x' inputs.
+    pub fn resized(&self, n_x: u64) -> Self {
+        Self::new_with_param(
+            n_x,
+            self.weight.nrows() as u64,
+            self.lambda,
+            self.alpha,
+            self.form,
+            self.symmetrize_interval,
+        )
+    }
+
+    /// Convert the optimizer and its state to another floating-point type.
+    pub fn to_precision<U: Float>(&self) -> RLS<U> {
+        RLS {
+            p: self.p.map(|v| cast(to_f64(v))),
+            lambda: cast(to_f64(self.lambda)),
+            alpha: cast(to_f64(self.alpha)),
+            weight: self.weight.map(|v| cast(to_f64(v))),
+            form: self.form,
+            symmetrize_interval: self.symmetrize_interval,
+            steps: self.steps,
+            diverged: self.diverged,
+        }
+    }
+
+    /// Return the covariance matrix P.
+    pub fn covariance(&self) -> na::DMatrix<T> {
+        match self.form {
+            RLSForm::Standard => self.p.clone(),
+            RLSForm::SquareRoot => &self.p * self.p.transpose(),
+        }
+    }
+
+    /// Return the gain k = P x / (lambda + x^T P x) and the updated P = (P - k x^T P) / lambda.
+    fn standard_update(&self, x: &na::DVector<T>) -> (na::DVector<T>, na::DMatrix<T>) {
+        let p_x = &self.p * x;
+        let gain = &p_x / (self.lambda + x.dot(&p_x));
+        let p = (&self.p - &gain * p_x.transpose()) / self.lambda;
+
+        (gain, p)
+    }
+
+    /// Return the gain and the updated factor S by Potter's square-root update.
+    fn square_root_update(&self, x: &na::DVector<T>) -> (na::DVector<T>, na::DMatrix<T>) {
+        let phi = self.p.tr_mul(x);
+        let beta = self.lambda + phi.norm_squared();
+        let alpha = T::one() / (beta + (self.lambda * beta).sqrt());
+
+        let s_phi = &self.p * &phi;
+        let gain = &s_phi / beta;
+        let s = (&self.p - s_phi * phi.transpose() * alpha) / self.lambda.sqrt();
+
+        (gain, s)
+    }
 }
 
-impl std::fmt::Display for RLS {
+impl<T: Float> std::fmt::Display for RLS<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let mut displayed = format!("Lambda: {}", self.lambda);
-        displayed.push_str(&format!("\nP:\n{}", self.p));
+        displayed.push_str(&format!("\nP:\n{}", self.covariance()));
         displayed.push_str(&format!("\nWeight:\n{}", self.weight));
         write!(f, "{}", displayed)
     }
 }
+
+impl<T: Float> Optimizer<T> for RLS<T> {
+    fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        RLS::set_data(self, x, d)
+    }
+
+    fn fit(&self) -> Result<na::DMatrix<T>> {
+        Ok(RLS::fit(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_square_root_matches_standard() {
+        let mut standard = RLS::new(3, 1, 0.98, 1.0);
+        let mut square_root = RLS::square_root(3, 1, 0.98, 1.0);
+        for n in 0..200 {
+            let t = n as f64;
+            let x = na::DVector::from_vec(vec![(0.3 * t).sin(), (0.7 * t).cos(), 1.0]);
+            let d = na::DVector::from_vec(vec![0.5 * x[0] - 2.0 * x[1] + 0.1]);
+            standard.set_data(&x, &d).unwrap();
+            square_root.set_data(&x, &d).unwrap();
+        }
+
+        for (s, r) in standard.fit().iter().zip(square_root.fit().iter()) {
+            assert_approx_eq!(s, r, 1e-9);
+        }
+        assert_approx_eq!(square_root.fit()[(0, 1)], -2.0, 1e-2);
+    }
+
+    #[test]
+    fn test_matches_least_squares() {
+        let (alpha, n_samples) = (0.01, 20);
+        let xs = (0..n_samples)
+            .map(|n| {
+                let t = n as f64;
+                na::DVector::from_vec(vec![(0.5 * t).sin(), (0.3 * t).cos(), 1.0])
+            })
+            .collect::<Vec<_>>();
+        let ds = xs
+            .iter()
+            .enumerate()
+            .map(|(n, x)| na::DVector::from_vec(vec![x[0] - 0.5 * x[1] + 0.01 * n as f64]))
+            .collect::<Vec<_>>();
+
+        for lambda in [1.0, 0.9] {
+            let mut rls = RLS::new(3, 1, lambda, alpha);
+            for (x, d) in xs.iter().zip(ds.iter()) {
+                rls.set_data(x, d).unwrap();
+            }
+
+            // The minimizer of sum_n lambda^(N-n) |d_n - W x_n|^2 + lambda^N alpha |W|^2
+            let mut xx = na::DMatrix::identity(3, 3) * lambda.powi(n_samples) * alpha;
+            let mut dx = na::DMatrix::zeros(1, 3);
+            for (n, (x, d)) in xs.iter().zip(ds.iter()).enumerate() {
+                let w = lambda.powi(n_samples - 1 - n as i32);
+                xx += x * x.transpose() * w;
+                dx += d * x.transpose() * w;
+            }
+            let expected = dx * xx.try_inverse().unwrap();
+
+            for (e, a) in expected.iter().zip(rls.fit().iter()) {
+                assert_approx_eq!(e, a, 1e-8);
+            }
+        }
+    }
+
+    #[test]
+    fn test_diverged_update_is_rejected() {
+        let mut rls = RLS::new(2, 1, 1.0, 1.0);
+        rls.set_data(
+            &na::DVector::from_vec(vec![1.0, 2.0]),
+            &na::DVector::from_vec(vec![1.0]),
+        )
+        .unwrap();
+        let weight = rls.fit();
+
+        let result = rls.set_data(
+            &na::DVector::from_vec(vec![f64::NAN, 1.0]),
+            &na::DVector::from_vec(vec![1.0]),
+        );
+
+        assert!(matches!(result, Err(EsnError::Diverged(_))));
+        assert!(rls.is_diverged());
+        assert_eq!(rls.fit(), weight);
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::VecDeque;
+
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::{EsnError, Float, Optimizer, Result, cast, check_dimension, to_f64};
+
+/// How the windowed Ridge regression forgets old samples.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RidgeMemory {
+    /// The statistics are multiplied by 'forgetting_factor' in (0, 1] before each new sample.
+    Exponential { forgetting_factor: f64 },
+    /// Only the last 'length' samples are kept, older ones being removed by downdates.
+    SlidingWindow { length: usize },
+}
+
+/// Ridge regression model on a window of recent samples, for non-stationary data.
+/// The statistics are updated at each sample like `Ridge`, but old samples are forgotten,
+/// so that `fit` can be called periodically to follow a drift.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+pub struct WindowedRidge<T = f64> {
+    beta: T,
+    memory: RidgeMemory,
+    x_xt: na::DMatrix<T>,
+    d_xt: na::DMatrix<T>,
+    /// Samples in the sliding window, oldest first
+    window: VecDeque<(na::DVector<T>, na::DVector<T>)>,
+    /// The number of downdates since the statistics were last recomputed from the window
+    downdates: usize,
+    /// The number of samples seen with exponential forgetting
+    #[serde(default)]
+    n_seen: u64,
+}
+
+impl<T: Float> WindowedRidge<T> {
+    /// Create a new Ridge regression model with exponential forgetting.
+    /// 'n_x' is the number of input variables and 'n_y' is the number of output variables.
+    /// 'beta' is the regularization parameter.
+    pub fn exponential(n_x: u64, n_y: u64, beta: T, forgetting_factor: f64) -> Result<Self> {
+        if !(forgetting_factor > 0.0 && forgetting_factor <= 1.0) {
+            return Err(EsnError::InvalidParameter(
+                "The forgetting factor must be in (0, 1]".to_string(),
+            ));
+        }
+        Ok(Self::new(
+            n_x,
+            n_y,
+            beta,
+            RidgeMemory::Exponential { forgetting_factor },
+        ))
+    }
+
+    /// Create a new Ridge regression model on a sliding window of the last 'length' samples.
+    pub fn sliding_window(n_x: u64, n_y: u64, beta: T, length: usize) -> Result<Self> {
+        if length == 0 {
+            return Err(EsnError::InvalidParameter(
+                "The window length must be positive".to_string(),
+            ));
+        }
+        Ok(Self::new(
+            n_x,
+            n_y,
+            beta,
+            RidgeMemory::SlidingWindow { length },
+        ))
+    }
+
+    fn new(n_x: u64, n_y: u64, beta: T, memory: RidgeMemory) -> Self {
+        WindowedRidge {
+            beta,
+            memory,
+            x_xt: na::DMatrix::zeros(n_x as usize, n_x as usize),
+            d_xt: na::DMatrix::zeros(n_y as usize, n_x as usize),
+            window: VecDeque::new(),
+            downdates: 0,
+            n_seen: 0,
+        }
+    }
+
+    /// Update the internal state with a new sample, forgetting old ones.
+    pub fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        check_dimension(self.x_xt.ncols(), x.len())?;
+        check_dimension(self.d_xt.nrows(), d.len())?;
+
+        match self.memory {
+            RidgeMemory::Exponential { forgetting_factor } => {
+                let forgetting_factor = cast::<T>(forgetting_factor);
+                self.x_xt.ger(T::one(), x, x, forgetting_factor);
+                self.d_xt.ger(T::one(), d, x, forgetting_factor);
+                self.n_seen += 1;
+            }
+            RidgeMemory::SlidingWindow { length } => {
+                self.x_xt.ger(T::one(), x, x, T::one());
+                self.d_xt.ger(T::one(), d, x, T::one());
+                self.window.push_back((x.clone(), d.clone()));
+
+                while self.window.len() > length {
+                    let (old_x, old_d) = self.window.pop_front().unwrap();
+                    self.x_xt.ger(-T::one(), &old_x, &old_x, T::one());
+                    self.d_xt.ger(-T::one(), &old_d, &old_x, T::one());
+                    self.downdates += 1;
+                }
+
+                // Recompute the statistics from time to time
+                // so that the rounding errors of the downdates do not build up.
+                if self.downdates >= length {
+                    self.recompute();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fit the model on the current window and return the weight matrix.
+    pub fn fit(&self) -> Result<na::DMatrix<T>> {
+        let n_x = self.x_xt.ncols();
+        let x_xt_inv = (self.x_xt.clone() + na::DMatrix::identity(n_x, n_x) * self.beta)
+            .try_inverse()
+            .ok_or(EsnError::SingularMatrix)?;
+
+        Ok(self.d_xt.clone() * x_xt_inv)
+    }
+
+    /// Add the samples of another model with the same parameters.
+    /// The samples of 'other' are considered newer: with exponential forgetting,
+    /// the statistics of 'self' are forgotten over the samples of 'other' before adding them.
+    pub fn merge(&mut self, other: &WindowedRidge<T>) -> Result<()> {
+        if self.beta != other.beta || self.memory != other.memory {
+            return Err(EsnError::Incompatible("WindowedRidge parameters"));
+        }
+        if self.d_xt.shape() != other.d_xt.shape() {
+            return Err(EsnError::ShapeMismatch {
+                expected: self.d_xt.shape(),
+                actual: other.d_xt.shape(),
+            });
+        }
+        match self.memory {
+            RidgeMemory::Exponential { forgetting_factor } => {
+                let decay = cast::<T>(forgetting_factor.powf(other.n_seen as f64));
+                self.x_xt = &self.x_xt * decay + &other.x_xt;
+                self.d_xt = &self.d_xt * decay + &other.d_xt;
+                self.n_seen += other.n_seen;
+            }
+            RidgeMemory::SlidingWindow { .. } => {
+                for (x, d) in other.window.iter() {
+                    self.set_data(x, d)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Clear the accumulated statistics.
+    pub fn reset(&mut self) {
+        self.x_xt.fill(T::zero());
+        self.d_xt.fill(T::zero());
+        self.window.clear();
+        self.downdates = 0;
+        self.n_seen = 0;
+    }
+
+    /// Return the number of samples in the window,
+    /// or with exponential forgetting the number of samples seen since the last reset, old ones included.
+    pub fn n_samples(&self) -> u64 {
+        match self.memory {
+            RidgeMemory::Exponential { .. } => self.n_seen,
+            RidgeMemory::SlidingWindow { .. } => self.window.len() as u64,
+        }
+    }
+
+    /// Return the number of input and output variables.
+    pub fn shape(&self) -> (u64, u64) {
+        (self.x_xt.ncols() as u64, self.d_xt.nrows() as u64)
+    }
+
+    /// Return a new model with the same parameters for 'n_x' inputs, without the accumulated statistics.
+    pub fn resized(&self, n_x: u64) -> Self {
+        Self::new(n_x, self.d_xt.nrows() as u64, self.beta, self.memory)
+    }
+
+    /// Convert the model and its accumulated statistics to another floating-point type.
+    pub fn to_precision<U: Float>(&self) -> WindowedRidge<U> {
+        WindowedRidge {
+            beta: cast(to_f64(self.beta)),
+            memory: self.memory,
+            x_xt: self.x_xt.map(|v| cast(to_f64(v))),
+            d_xt: self.d_xt.map(|v| cast(to_f64(v))),
+            window: self
+                .window
+                .iter()
+                .map(|(x, d)| (x.map(|v| cast(to_f64(v))), d.map(|v| cast(to_f64(v)))))
+                .collect(),
+            downdates: self.downdates,
+            n_seen: self.n_seen,
+        }
+    }
+
+    fn recompute(&mut self) {
+        self.x_xt.fill(T::zero());
+        self.d_xt.fill(T::zero());
+        for (x, d) in self.window.iter() {
+            self.x_xt.ger(T::one(), x, x, T::one());
+            self.d_xt.ger(T::one(), d, x, T::one());
+        }
+        self.downdates = 0;
+    }
+}
+
+impl<T: Float> Optimizer<T> for WindowedRidge<T> {
+    fn set_data(&mut self, x: &na::DVector<T>, d: &na::DVector<T>) -> Result<()> {
+        WindowedRidge::set_data(self, x, d)
+    }
+
+    fn fit(&self) -> Result<na::DMatrix<T>> {
+        WindowedRidge::fit(self)
+    }
+}
+
+impl<T: Float> std::fmt::Display for WindowedRidge<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut displayed = format!("Beta: {}", self.beta);
+        displayed.push_str(&format!("\nMemory: {:?}", self.memory));
+        displayed.push_str(&format!("\nx_xt:\n{}", self.x_xt));
+        displayed.push_str(&format!("\nd_xt:\n{}", self.d_xt));
+        write!(f, "{}", displayed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_sliding_window_matches_ridge_on_last_samples() {
+        let samples = (0..20)
+            .map(|n| {
+                let t = n as f64;
+                let x = na::DVector::from_vec(vec![(0.5 * t).sin(), 1.0]);
+                let gain = if n < 10 { 1.0 } else { -2.0 };
+                let d = na::DVector::from_vec(vec![gain * x[0]]);
+                (x, d)
+            })
+            .collect::<Vec<_>>();
+
+        let mut windowed = WindowedRidge::sliding_window(2, 1, 0.01, 5).unwrap();
+        for (x, d) in samples.iter() {
+            windowed.set_data(x, d).unwrap();
+        }
+        let mut ridge = crate::Ridge::new(2, 1, 0.01);
+        for (x, d) in samples[15..].iter() {
+            ridge.set_data(x, d).unwrap();
+        }
+
+        assert_eq!(windowed.n_samples(), 5);
+        for (e, a) in ridge
+            .fit()
+            .unwrap()
+            .iter()
+            .zip(windowed.fit().unwrap().iter())
+        {
+            assert_approx_eq!(e, a);
+        }
+    }
+
+    #[test]
+    fn test_exponential_forgetting_follows_a_change() {
+        let sample = |n: usize| {
+            let x = na::DVector::from_vec(vec![(0.5 * n as f64).sin(), 1.0]);
+            let gain = if n < 200 { 1.0 } else { -2.0 };
+            let d = na::DVector::from_vec(vec![gain * x[0]]);
+            (x, d)
+        };
+
+        let mut windowed = WindowedRidge::exponential(2, 1, 1e-6, 0.9).unwrap();
+        for n in 0..200 {
+            let (x, d) = sample(n);
+            windowed.set_data(&x, &d).unwrap();
+        }
+        assert_approx_eq!(windowed.fit().unwrap()[(0, 0)], 1.0, 1e-4);
+
+        // The older samples decay by 0.9^200 after the change.
+        for n in 200..400 {
+            let (x, d) = sample(n);
+            windowed.set_data(&x, &d).unwrap();
+        }
+        assert_eq!(windowed.n_samples(), 400);
+        assert_approx_eq!(windowed.fit().unwrap()[(0, 0)], -2.0, 1e-4);
+    }
+
+    #[test]
+    fn test_exponential_merge_matches_sequential_updates() {
+        let samples = (0..30)
+            .map(|n| {
+                let t = n as f64;
+                let x = na::DVector::from_vec(vec![(0.5 * t).sin(), (0.2 * t).cos()]);
+                let d = na::DVector::from_vec(vec![x[0] - t * 0.1 * x[1]]);
+                (x, d)
+            })
+            .collect::<Vec<_>>();
+
+        let mut sequential = WindowedRidge::exponential(2, 1, 0.01, 0.8).unwrap();
+        let mut first = WindowedRidge::exponential(2, 1, 0.01, 0.8).unwrap();
+        let mut second = WindowedRidge::exponential(2, 1, 0.01, 0.8).unwrap();
+        for (n, (x, d)) in samples.iter().enumerate() {
+            sequential.set_data(x, d).unwrap();
+            if n < 20 {
+                first.set_data(x, d).unwrap();
+            } else {
+                second.set_data(x, d).unwrap();
+            }
+        }
+        first.merge(&second).unwrap();
+
+        assert_eq!(first.n_samples(), 30);
+        for (e, a) in sequential
+            .fit()
+            .unwrap()
+            .iter()
+            .zip(first.fit().unwrap().iter())
+        {
+            assert_approx_eq!(e, a);
+        }
+    }
+}
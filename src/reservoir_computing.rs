@@ -1,14 +1,80 @@
 mod echo_state_network;
+mod liquid_state_machine;
+mod mass_spring;
+mod minimum_complexity;
+mod multi_stream;
+mod ng_reservoir;
 mod physical_reservoir;
+mod teacher_forcing;
+mod time_delay;
 
 pub use echo_state_network::*;
+pub use liquid_state_machine::*;
+pub use mass_spring::*;
+pub use minimum_complexity::*;
+pub use multi_stream::*;
+pub use ng_reservoir::*;
 pub use physical_reservoir::*;
+pub use teacher_forcing::*;
+pub use time_delay::*;
 
-pub trait ReservoirComputing {
+use nalgebra as na;
+
+use crate::{Float, Result, check_dimension, check_samples};
+
+/// Common interface of the reservoir computing models.
+/// The trait is object safe, so different models can be used as `Box<dyn ReservoirComputing>`.
+pub trait ReservoirComputing<T: Float = f64> {
     /// Online training method.
-    fn train(&mut self, teaching_input: &[f64], teaching_output: &[f64]);
+    /// teaching_input: A sample of the readout features (n_features), e.g. a column harvested by `harvest_states`,
+    /// not a raw input; the features depend on the state of the model.
+    fn train(&mut self, teaching_input: &[T], teaching_output: &[T]) -> Result<()>;
     /// Offline training method.
-    fn offline_train(&mut self, teaching_input: &[Vec<f64>], teaching_output: &[Vec<f64>]);
+    /// Harvest the states of the teaching sequence and fit the readout on them.
+    fn offline_train(
+        &mut self,
+        teaching_input: &[Vec<T>],
+        teaching_output: &[Vec<T>],
+    ) -> Result<()> {
+        let (states, targets) = self.harvest_states(teaching_input, teaching_output)?;
+        self.fit_from_states(&states, &targets)
+    }
     /// Estimate method.
-    fn estimate(&mut self, input: &[f64]) -> Vec<f64>;
+    fn estimate(&mut self, input: &[T]) -> Result<Vec<T>>;
+    /// Estimate the outputs of a sequence of inputs given one per column (n_inputs x N),
+    /// and return them one per column (n_outputs x N).
+    fn estimate_sequence(&mut self, inputs: &na::DMatrix<T>) -> Result<na::DMatrix<T>> {
+        check_dimension(self.n_inputs() as usize, inputs.nrows())?;
+        let mut outputs = na::DMatrix::zeros(self.n_outputs() as usize, inputs.ncols());
+        for (n, input) in inputs.column_iter().enumerate() {
+            let output = self.estimate(input.clone_owned().as_slice())?;
+            outputs.set_column(n, &na::DVector::from_vec(output));
+        }
+        Ok(outputs)
+    }
+    /// Estimate the outputs of a sequence of input samples, one output per column.
+    fn estimate_batch(&mut self, inputs: &[Vec<T>]) -> Result<na::DMatrix<T>> {
+        let n_inputs = self.n_inputs() as usize;
+        check_samples(inputs, n_inputs)?;
+        let inputs = na::DMatrix::from_fn(n_inputs, inputs.len(), |i, n| inputs[n][i]);
+        self.estimate_sequence(&inputs)
+    }
+    /// Run the model on a teaching sequence,
+    /// and return the states and the targets of the readout, one sample per column.
+    fn harvest_states(
+        &mut self,
+        teaching_input: &[Vec<T>],
+        teaching_output: &[Vec<T>],
+    ) -> Result<(na::DMatrix<T>, na::DMatrix<T>)>;
+    /// Fit the readout by the offline optimizer on harvested states and targets.
+    /// The states are added to the data already accumulated by the optimizer.
+    fn fit_from_states(&mut self, states: &na::DMatrix<T>, targets: &na::DMatrix<T>) -> Result<()>;
+    /// Reset the internal state of the model, keeping the trained readout.
+    fn reset(&mut self);
+    /// Return the number of input variables.
+    fn n_inputs(&self) -> u64;
+    /// Return the number of output variables.
+    fn n_outputs(&self) -> u64;
+    /// Return the number of features of the readout, i.e. the length of a sample given to `train`.
+    fn n_features(&self) -> u64;
 }
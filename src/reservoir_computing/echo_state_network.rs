@@ -1,21 +1,36 @@
 use nalgebra as na;
 
+use serde::{Deserialize, Serialize};
+
 use crate::*;
 
-pub struct EchoStateNetwork {
-    input: Input,
-    reservoir: Reservoir,
-    output: Output,
-    previous_y: na::DVector<f64>,
-    output_function: fn(&na::DVector<f64>) -> na::DVector<f64>,
-    inverse_output_function: fn(&na::DVector<f64>) -> na::DVector<f64>,
+/// The offline optimizer saved with the weights its accumulated states depend on.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Float", deserialize = "T: Float"))]
+struct OfflineTraining<T> {
+    input: Input<T>,
+    reservoir: Reservoir<T>,
+    feedback: Option<Feedback<T>>,
+    optimizer: OfflineOptimizer<T>,
+}
+
+pub struct EchoStateNetwork<T = f64> {
+    input: Input<T>,
+    reservoir: Reservoir<T>,
+    output: Output<T>,
+    previous_y: na::DVector<T>,
+    output_function: fn(&na::DVector<T>) -> na::DVector<T>,
+    inverse_output_function: fn(&na::DVector<T>) -> na::DVector<T>,
     is_classification: bool,
     n_y: u64,
     n_u: u64,
-    feedback: Option<Feedback>,
+    n_x: u64,
+    feedback: Option<Feedback<T>>,
     is_noisy: bool,
-    rls: Option<RLS>,
-    ridge: Option<Ridge>,
+    teacher_forcing: TeacherForcing,
+    features: ReadoutFeatures,
+    online_optimizer: Option<OnlineOptimizer<T>>,
+    offline_optimizer: Option<OfflineOptimizer<T>>,
 }
 
 impl EchoStateNetwork {
@@ -34,9 +49,75 @@ impl EchoStateNetwork {
         output_function: fn(&na::DVector<f64>) -> na::DVector<f64>,
         inverse_output_function: fn(&na::DVector<f64>) -> na::DVector<f64>,
         is_classification: bool,
-        ridge_beta: f64,
-    ) -> Self {
-        EchoStateNetwork {
+        readout: ReadoutConfig,
+    ) -> Result<Self> {
+        Self::new_with_precision(
+            n_u,
+            n_y,
+            n_x,
+            density,
+            input_scale,
+            rho,
+            activation,
+            feedback_scale,
+            noise_level,
+            leaking_rate,
+            output_function,
+            inverse_output_function,
+            is_classification,
+            readout,
+        )
+    }
+
+    /// Create a new EchoStateNetwork with a deterministic minimum-complexity reservoir,
+    /// e.g. a simple cycle reservoir, without feedback and with a zero readout before training.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_minimum_complexity(
+        n_u: u64,
+        n_y: u64,
+        n_x: u64,
+        reservoir: MinimumComplexityReservoir,
+        activation: fn(f64) -> f64,
+        leaking_rate: f64,
+        output_function: fn(&na::DVector<f64>) -> na::DVector<f64>,
+        inverse_output_function: fn(&na::DVector<f64>) -> na::DVector<f64>,
+        readout: ReadoutConfig,
+    ) -> Result<Self> {
+        Self::new_minimum_complexity_with_precision(
+            n_u,
+            n_y,
+            n_x,
+            reservoir,
+            activation,
+            leaking_rate,
+            output_function,
+            inverse_output_function,
+            readout,
+        )
+    }
+}
+
+impl<T: Float> EchoStateNetwork<T> {
+    /// Create a new EchoStateNetwork computing in the floating-point type 'T',
+    /// e.g. `EchoStateNetwork::<f32>::new_with_precision(...).unwrap()`. The parameters are the same as `new`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_precision(
+        n_u: u64,
+        n_y: u64,
+        n_x: u64,
+        density: f64,
+        input_scale: f64,
+        rho: f64,
+        activation: fn(T) -> T,
+        feedback_scale: Option<f64>,
+        noise_level: Option<f64>,
+        leaking_rate: f64,
+        output_function: fn(&na::DVector<T>) -> na::DVector<T>,
+        inverse_output_function: fn(&na::DVector<T>) -> na::DVector<T>,
+        is_classification: bool,
+        readout: ReadoutConfig,
+    ) -> Result<Self> {
+        Ok(EchoStateNetwork {
             input: Input::new(n_u, n_x, input_scale),
             reservoir: Reservoir::new(n_x, density, rho, activation, leaking_rate, None),
             output: Output::new(n_y, n_x),
@@ -46,19 +127,60 @@ impl EchoStateNetwork {
             is_classification,
             n_y,
             n_u,
+            n_x,
             feedback: feedback_scale.map(|scale| Feedback::new(n_y, n_x, scale)),
             is_noisy: noise_level.is_some(),
-            rls: Some(RLS::new(n_x, n_y, 1.0, 1.0)),
-            ridge: Some(Ridge::new(n_x, n_y, ridge_beta)),
-        }
+            teacher_forcing: TeacherForcing::default(),
+            features: ReadoutFeatures::Linear,
+            online_optimizer: Some(readout.online.build(n_x, n_y)?),
+            offline_optimizer: Some(readout.offline.build(n_x, n_y)?),
+        })
+    }
+
+    /// Create a new EchoStateNetwork with a minimum-complexity reservoir computing in the floating-point type 'T'.
+    /// The parameters are the same as `new_minimum_complexity`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_minimum_complexity_with_precision(
+        n_u: u64,
+        n_y: u64,
+        n_x: u64,
+        reservoir: MinimumComplexityReservoir,
+        activation: fn(T) -> T,
+        leaking_rate: f64,
+        output_function: fn(&na::DVector<T>) -> na::DVector<T>,
+        inverse_output_function: fn(&na::DVector<T>) -> na::DVector<T>,
+        readout: ReadoutConfig,
+    ) -> Result<Self> {
+        Ok(EchoStateNetwork {
+            input: Input::from_weight(reservoir.input_weight(n_u, n_x).map(cast)),
+            reservoir: Reservoir::from_weight(
+                reservoir.reservoir_weight(n_x)?.map(cast),
+                activation,
+                leaking_rate,
+            ),
+            output: Output::zeros(n_y, n_x),
+            previous_y: na::DVector::zeros(n_y as usize),
+            output_function,
+            inverse_output_function,
+            is_classification: false,
+            n_y,
+            n_u,
+            n_x,
+            feedback: None,
+            is_noisy: false,
+            teacher_forcing: TeacherForcing::default(),
+            features: ReadoutFeatures::Linear,
+            online_optimizer: Some(readout.online.build(n_x, n_y)?),
+            offline_optimizer: Some(readout.offline.build(n_x, n_y)?),
+        })
     }
 
-    pub fn serde_json(&self) -> serde_json::Result<String> {
+    pub fn serde_json(&self) -> Result<String> {
         let input = serde_json::to_string(&self.input)?;
         let reservoir = serde_json::to_string(&self.reservoir)?;
         let output = serde_json::to_string(&self.output)?;
-        let feedback = if let Some(fdb) = self.feedback.clone() {
-            serde_json::to_string(&fdb)?
+        let feedback = if let Some(fdb) = self.feedback.as_ref() {
+            serde_json::to_string(fdb)?
         } else {
             "None".to_string()
         };
@@ -73,33 +195,278 @@ impl EchoStateNetwork {
         );
         Ok(json)
     }
-}
 
-impl ReservoirComputing for EchoStateNetwork {
-    fn train(&mut self, teaching_input: &[f64], teaching_output: &[f64]) {
-        if self.rls.is_none() {
-            panic!("RLS is not initialized");
+    pub fn readout_weight(&self) -> &na::DMatrix<T> {
+        self.output.output_weight()
+    }
+
+    /// Convert the trained network to another floating-point type, e.g. to run inference in f32.
+    /// The activation and output functions are given again, since they depend on the type.
+    /// The optimizers are converted with their accumulated data, so the training can be resumed.
+    pub fn to_precision<U: Float>(
+        &self,
+        activation: fn(U) -> U,
+        output_function: fn(&na::DVector<U>) -> na::DVector<U>,
+        inverse_output_function: fn(&na::DVector<U>) -> na::DVector<U>,
+    ) -> EchoStateNetwork<U> {
+        EchoStateNetwork {
+            input: self.input.to_precision(),
+            reservoir: self.reservoir.to_precision(activation),
+            output: self.output.to_precision(),
+            previous_y: self.previous_y.map(|v| cast(to_f64(v))),
+            output_function,
+            inverse_output_function,
+            is_classification: self.is_classification,
+            n_y: self.n_y,
+            n_u: self.n_u,
+            n_x: self.n_x,
+            feedback: self.feedback.as_ref().map(Feedback::to_precision),
+            is_noisy: self.is_noisy,
+            teacher_forcing: self.teacher_forcing,
+            features: self.features.clone(),
+            online_optimizer: self.online_optimizer.as_ref().map(|o| o.to_precision()),
+            offline_optimizer: self.offline_optimizer.as_ref().map(|o| o.to_precision()),
         }
+    }
 
-        let x = na::DVector::from_vec(teaching_input.to_vec());
-        let d = na::DVector::from_vec(teaching_output.to_vec());
+    /// Set how the output is fed back while harvesting the states of a teaching sequence,
+    /// e.g. with noise or scheduled sampling so that the network stays stable when it runs closed-loop.
+    /// The scheduled sampling only starts once the readout has been fitted.
+    pub fn set_teacher_forcing(&mut self, teacher_forcing: TeacherForcing) -> Result<()> {
+        teacher_forcing.validate()?;
+        self.teacher_forcing = teacher_forcing;
+        Ok(())
+    }
 
-        self.rls.as_mut().unwrap().set_data(&x, &d);
-        let weight = self.rls.as_ref().unwrap().fit();
-        self.output.set_weight(weight);
+    /// Replace both optimizers with new ones built from the configuration.
+    pub fn set_readout_config(&mut self, readout: ReadoutConfig) -> Result<()> {
+        let n_features = self.n_features();
+        let online_optimizer = readout.online.build(n_features, self.n_y)?;
+        let offline_optimizer = readout.offline.build(n_features, self.n_y)?;
+        self.online_optimizer = Some(online_optimizer);
+        self.offline_optimizer = Some(offline_optimizer);
+        Ok(())
     }
 
-    fn offline_train(&mut self, teaching_input: &[Vec<f64>], teaching_output: &[Vec<f64>]) {
-        if self.ridge.is_none() {
-            panic!("Ridge is not initialized");
+    /// Set the feature map applied to the reservoir state before the readout, e.g. `ReadoutFeatures::Squared`.
+    /// The readout weight is reset and the optimizers are replaced by new ones of the same kinds and parameters,
+    /// without their accumulated data, so it is set before the training.
+    pub fn set_readout_features(&mut self, features: ReadoutFeatures) -> Result<()> {
+        features.validate(self.n_x)?;
+        let n_features = features.n_features(self.n_x);
+        let online_optimizer = self
+            .online_optimizer
+            .as_ref()
+            .map(|o| o.resized(n_features));
+        let offline_optimizer = self
+            .offline_optimizer
+            .as_ref()
+            .map(|o| o.resized(n_features))
+            .transpose()?;
+
+        self.features = features;
+        self.output = Output::new(self.n_y, n_features);
+        self.online_optimizer = online_optimizer;
+        self.offline_optimizer = offline_optimizer;
+        Ok(())
+    }
+
+    /// Start the online optimizer from the current readout weight,
+    /// e.g. to fine-tune online a readout trained offline.
+    pub fn warm_start_online_optimizer(&mut self) -> Result<()> {
+        match self.online_optimizer.as_mut() {
+            Some(optimizer) => optimizer.set_weight(self.output.output_weight().clone()),
+            None => Err(EsnError::NotInitialized("online optimizer")),
         }
+    }
 
-        let train_length = teaching_input.len();
-        let input_elements = teaching_input
+    /// Return the optimizer used by the offline training and its accumulated data.
+    pub fn offline_optimizer(&self) -> Option<&OfflineOptimizer<T>> {
+        self.offline_optimizer.as_ref()
+    }
+
+    /// Save the offline optimizer with its accumulated data, e.g. after `offline_train`.
+    /// The input, reservoir and feedback weights are saved with it, since the accumulated states depend on them.
+    pub fn save_offline_optimizer(&self, path: &str) -> Result<()> {
+        let optimizer = self
+            .offline_optimizer
+            .as_ref()
+            .ok_or(EsnError::NotInitialized("offline optimizer"))?;
+        let training = OfflineTraining {
+            input: self.input.clone(),
+            reservoir: self.reservoir.clone(),
+            feedback: self.feedback.clone(),
+            optimizer: optimizer.clone(),
+        };
+        std::fs::write(path, serde_json::to_string(&training)?)?;
+
+        Ok(())
+    }
+
+    /// Load an offline optimizer saved by `save_offline_optimizer`.
+    /// The input, reservoir and feedback weights are replaced by the saved ones,
+    /// so the training can be resumed by another process.
+    /// The following `offline_train` adds the new data to the loaded one before fitting.
+    pub fn load_offline_optimizer(&mut self, path: &str) -> Result<()> {
+        let json = std::fs::read_to_string(path)?;
+        let training: OfflineTraining<T> = serde_json::from_str(&json)?;
+
+        let (n_x, n_y) = training.optimizer.shape();
+        if (n_x, n_y) != (self.n_features(), self.n_y) {
+            return Err(EsnError::ShapeMismatch {
+                expected: (self.n_y as usize, self.n_features() as usize),
+                actual: (n_y as usize, n_x as usize),
+            });
+        }
+        if training.input.shape() != self.input.shape() {
+            return Err(EsnError::ShapeMismatch {
+                expected: self.input.shape(),
+                actual: training.input.shape(),
+            });
+        }
+        if training.reservoir.n_x() != self.reservoir.n_x() {
+            return Err(EsnError::DimensionMismatch {
+                expected: self.reservoir.n_x(),
+                actual: training.reservoir.n_x(),
+            });
+        }
+        match (self.feedback.as_ref(), training.feedback.as_ref()) {
+            (None, None) => {}
+            (Some(feedback), Some(saved)) if feedback.shape() != saved.shape() => {
+                return Err(EsnError::ShapeMismatch {
+                    expected: feedback.shape(),
+                    actual: saved.shape(),
+                });
+            }
+            (Some(_), Some(_)) => {}
+            _ => return Err(EsnError::Incompatible("feedback")),
+        }
+
+        self.input = training.input;
+        self.reservoir.copy_weight_from(&training.reservoir);
+        self.feedback = training.feedback;
+        self.offline_optimizer = Some(training.optimizer);
+        Ok(())
+    }
+
+    /// Replace the optimizer used by the online training.
+    /// e.g. an `LMS` when RLS is too expensive for the reservoir size.
+    pub fn set_online_optimizer(&mut self, optimizer: impl Into<OnlineOptimizer<T>>) {
+        self.online_optimizer = Some(optimizer.into());
+    }
+
+    /// Replace the optimizer used by the offline training.
+    /// With a `LogisticRegression`, `estimate` returns class probabilities;
+    /// the output functions should then be the identity so the class labels are used as they are.
+    pub fn set_offline_optimizer(&mut self, optimizer: impl Into<OfflineOptimizer<T>>) {
+        self.offline_optimizer = Some(optimizer.into());
+    }
+
+    /// Create a multi-stream network sharing the weights of this network, without any stream.
+    /// The streams are then added by `MultiStreamEchoStateNetwork::add_stream`.
+    pub fn multi_stream(&self) -> Result<MultiStreamEchoStateNetwork<T>> {
+        if self.is_noisy {
+            return Err(EsnError::Unsupported("reservoir noise"));
+        }
+        if self.is_classification {
+            return Err(EsnError::Unsupported("classification"));
+        }
+        Ok(MultiStreamEchoStateNetwork::new(
+            self.input.clone(),
+            self.reservoir.clone(),
+            self.output.clone(),
+            self.feedback.clone(),
+            self.features.clone(),
+            self.output_function,
+            self.n_u,
+            self.n_y,
+        ))
+    }
+
+    /// Offline training on several independent sequences.
+    /// Each sequence is run from a zero reservoir state and its states are accumulated separately,
+    /// in parallel across CPU cores with the `rayon` feature, before the accumulators are merged.
+    /// The reservoir state of the model is left untouched.
+    pub fn offline_train_sequences(
+        &mut self,
+        teaching_inputs: &[Vec<Vec<T>>],
+        teaching_outputs: &[Vec<Vec<T>>],
+    ) -> Result<()> {
+        check_dimension(teaching_inputs.len(), teaching_outputs.len())?;
+
+        let mut empty = self
+            .offline_optimizer
+            .clone()
+            .ok_or(EsnError::NotInitialized("offline optimizer"))?;
+        empty.reset();
+
+        let accumulate = |(teaching_input, teaching_output): (&Vec<Vec<T>>, &Vec<Vec<T>>)| {
+            let mut reservoir = self.reservoir.clone();
+            reservoir.reset();
+            let mut previous_y = na::DVector::zeros(self.n_y as usize);
+            let (states, targets) = self.harvest(
+                &mut reservoir,
+                &mut previous_y,
+                teaching_input,
+                teaching_output,
+            )?;
+
+            let mut optimizer = empty.clone();
+            optimizer.set_batch(&states, &targets)?;
+            Ok(optimizer)
+        };
+
+        #[cfg(feature = "rayon")]
+        let accumulators = {
+            use rayon::prelude::*;
+            teaching_inputs
+                .par_iter()
+                .zip(teaching_outputs.par_iter())
+                .map(accumulate)
+                .collect::<Result<Vec<_>>>()?
+        };
+        #[cfg(not(feature = "rayon"))]
+        let accumulators = teaching_inputs
             .iter()
-            .flatten()
-            .cloned()
-            .collect::<Vec<f64>>();
+            .zip(teaching_outputs.iter())
+            .map(accumulate)
+            .collect::<Result<Vec<_>>>()?;
+
+        let optimizer = self.offline_optimizer.as_mut().unwrap();
+        for accumulator in accumulators.iter() {
+            optimizer.merge(accumulator)?;
+        }
+        self.output.set_weight(optimizer.fit()?);
+        self.output.set_link(optimizer.link());
+
+        Ok(())
+    }
+
+    /// Run the reservoir on a teaching sequence with teacher forcing,
+    /// and return the reservoir states and the transformed teaching outputs, one sample per column.
+    /// The teaching output is fed back in the output space, as `estimate` feeds back its output.
+    fn harvest(
+        &self,
+        reservoir: &mut Reservoir<T>,
+        previous_y: &mut na::DVector<T>,
+        teaching_input: &[Vec<T>],
+        teaching_output: &[Vec<T>],
+    ) -> Result<(na::DMatrix<T>, na::DMatrix<T>)> {
+        check_dimension(teaching_input.len(), teaching_output.len())?;
+        check_samples(teaching_input, self.n_u as usize)?;
+        check_samples(teaching_output, self.n_y as usize)?;
+
+        let train_length = teaching_input.len();
+        // The predictions of an unfitted readout are meaningless, so they are not sampled.
+        let teacher_forcing = if self.output.is_fitted() {
+            self.teacher_forcing
+        } else {
+            TeacherForcing {
+                sampling_probability: 0.0,
+                ..self.teacher_forcing
+            }
+        };
+        let input_elements = teaching_input.iter().flatten().cloned().collect::<Vec<T>>();
         let teaching_input = na::DMatrix::from_column_slice(
             self.n_u as usize,
             train_length,
@@ -109,57 +476,171 @@ impl ReservoirComputing for EchoStateNetwork {
             .iter()
             .flatten()
             .cloned()
-            .collect::<Vec<f64>>();
+            .collect::<Vec<T>>();
         let teaching_output = na::DMatrix::from_column_slice(
             self.n_y as usize,
             train_length,
             output_elements.as_slice(),
         );
 
+        let mut states = na::DMatrix::zeros(self.n_features() as usize, train_length);
+        let mut targets = na::DMatrix::zeros(self.n_y as usize, train_length);
+
         for n in 0..train_length {
             let mut x_in = self.input.call(&teaching_input.column(n).clone_owned());
 
-            if let Some(fdb) = self.feedback.clone() {
-                let x_fdb = fdb.give_feedback(&self.previous_y);
+            if let Some(fdb) = self.feedback.as_ref() {
+                let x_fdb = fdb.give_feedback(previous_y);
                 x_in += x_fdb;
             }
 
             if self.is_noisy {
-                todo!()
+                return Err(EsnError::Unsupported("reservoir noise"));
             }
 
-            let x_res = self.reservoir.call(x_in);
+            let x_res = self.features.apply(&reservoir.call(x_in));
 
             if self.is_classification {
-                todo!()
+                return Err(EsnError::Unsupported("classification"));
+            }
+
+            let y = teaching_output.column(n).clone_owned();
+            let d = (self.inverse_output_function)(&y);
+
+            states.set_column(n, &x_res);
+            targets.set_column(n, &d);
+
+            if self.feedback.is_some() {
+                *previous_y = teacher_forcing.feedback(
+                    y,
+                    || (self.output_function)(&self.output.call(&x_res)),
+                    n,
+                    train_length,
+                );
+            } else {
+                *previous_y = y;
+            }
+        }
+
+        Ok((states, targets))
+    }
+
+    /// FORCE (first-order reduced and controlled error) learning.
+    /// The network runs closed-loop on its own fed-back output
+    /// while the readout is updated by the online optimizer (RLS by default).
+    /// update_interval: The number of steps between two readout updates.
+    /// training_window: The steps in which the readout is updated. Outside of it the network only runs.
+    /// Return the closed-loop output of each step.
+    pub fn force_train(
+        &mut self,
+        teaching_input: &[Vec<T>],
+        teaching_output: &[Vec<T>],
+        update_interval: usize,
+        training_window: std::ops::Range<usize>,
+    ) -> Result<Vec<Vec<T>>> {
+        if self.feedback.is_none() {
+            return Err(EsnError::NotInitialized("feedback layer"));
+        }
+        if self.online_optimizer.is_none() {
+            return Err(EsnError::NotInitialized("online optimizer"));
+        }
+        if update_interval == 0 {
+            return Err(EsnError::InvalidParameter(
+                "The update interval must be positive".to_string(),
+            ));
+        }
+
+        let mut outputs = Vec::with_capacity(teaching_input.len());
+
+        for (n, (input, output)) in teaching_input
+            .iter()
+            .zip(teaching_output.iter())
+            .enumerate()
+        {
+            check_sample(n, input, self.n_u as usize)?;
+            check_sample(n, output, self.n_y as usize)?;
+            let u = na::DVector::from_column_slice(input);
+
+            let mut x_in = self.input.call(&u);
+            x_in += self
+                .feedback
+                .as_ref()
+                .unwrap()
+                .give_feedback(&self.previous_y);
+
+            let x_res = self.features.apply(&self.reservoir.call(x_in));
+
+            let y = self.output.call(&x_res);
+            let y = (self.output_function)(&y);
+
+            if training_window.contains(&n)
+                && (n - training_window.start).is_multiple_of(update_interval)
+            {
+                let d = na::DVector::from_column_slice(output);
+                let d = (self.inverse_output_function)(&d);
+
+                let optimizer = self.online_optimizer.as_mut().unwrap();
+                optimizer.set_data(&x_res, &d)?;
+                self.output.set_weight(optimizer.fit()?);
+                self.output.set_link(optimizer.link());
             }
 
-            let d = teaching_output.column(n).clone_owned();
-            let d = (self.inverse_output_function)(&d);
+            outputs.push(y.as_slice().to_vec());
+            self.previous_y = y;
+        }
 
-            self.ridge.as_mut().unwrap().set_data(&x_res, &d);
+        Ok(outputs)
+    }
+}
+
+impl<T: Float> ReservoirComputing<T> for EchoStateNetwork<T> {
+    fn train(&mut self, teaching_input: &[T], teaching_output: &[T]) -> Result<()> {
+        check_sample(0, teaching_input, self.n_features() as usize)?;
+        check_sample(0, teaching_output, self.n_y as usize)?;
+        let optimizer = self
+            .online_optimizer
+            .as_mut()
+            .ok_or(EsnError::NotInitialized("online optimizer"))?;
+
+        let x = na::DVector::from_vec(teaching_input.to_vec());
+        let d = na::DVector::from_vec(teaching_output.to_vec());
+
+        optimizer.set_data(&x, &d)?;
+        let weight = optimizer.fit()?;
+        self.output.set_weight(weight);
+        self.output.set_link(optimizer.link());
 
-            self.previous_y = d.clone();
+        Ok(())
+    }
+
+    fn offline_train(
+        &mut self,
+        teaching_input: &[Vec<T>],
+        teaching_output: &[Vec<T>],
+    ) -> Result<()> {
+        if self.offline_optimizer.is_none() {
+            return Err(EsnError::NotInitialized("offline optimizer"));
         }
 
-        let output_weight = self.ridge.as_mut().unwrap().fit();
-        self.output.set_weight(output_weight);
+        let (states, targets) = self.harvest_states(teaching_input, teaching_output)?;
+        self.fit_from_states(&states, &targets)
     }
 
-    fn estimate(&mut self, input: &[f64]) -> Vec<f64> {
+    fn estimate(&mut self, input: &[T]) -> Result<Vec<T>> {
+        check_sample(0, input, self.n_u as usize)?;
         let input = na::DVector::from_column_slice(input);
 
         let mut x_in = self.input.call(&input);
 
-        if let Some(fdb) = self.feedback.clone() {
+        if let Some(fdb) = self.feedback.as_ref() {
             let x_fdb = fdb.give_feedback(&self.previous_y);
             x_in += x_fdb;
         }
 
-        let x_res = self.reservoir.call(x_in);
+        let x_res = self.features.apply(&self.reservoir.call(x_in));
 
         if self.is_classification {
-            todo!()
+            return Err(EsnError::Unsupported("classification"));
         }
 
         let y_estimated = self.output.call(&x_res);
@@ -167,6 +648,311 @@ impl ReservoirComputing for EchoStateNetwork {
 
         self.previous_y = y_estimated.clone();
 
-        y_estimated.as_slice().to_vec()
+        Ok(y_estimated.as_slice().to_vec())
+    }
+
+    fn harvest_states(
+        &mut self,
+        teaching_input: &[Vec<T>],
+        teaching_output: &[Vec<T>],
+    ) -> Result<(na::DMatrix<T>, na::DMatrix<T>)> {
+        let mut reservoir = self.reservoir.clone();
+        let mut previous_y = self.previous_y.clone();
+        let harvested = self.harvest(
+            &mut reservoir,
+            &mut previous_y,
+            teaching_input,
+            teaching_output,
+        )?;
+        self.reservoir = reservoir;
+        self.previous_y = previous_y;
+
+        Ok(harvested)
+    }
+
+    fn fit_from_states(&mut self, states: &na::DMatrix<T>, targets: &na::DMatrix<T>) -> Result<()> {
+        let optimizer = self
+            .offline_optimizer
+            .as_mut()
+            .ok_or(EsnError::NotInitialized("offline optimizer"))?;
+        optimizer.set_batch(states, targets)?;
+        self.output.set_weight(optimizer.fit()?);
+        self.output.set_link(optimizer.link());
+
+        Ok(())
+    }
+
+    /// Reset the reservoir state and the fed-back output to zero.
+    fn reset(&mut self) {
+        self.reservoir.reset();
+        self.previous_y.fill(T::zero());
+    }
+
+    fn n_inputs(&self) -> u64 {
+        self.n_u
+    }
+
+    fn n_outputs(&self) -> u64 {
+        self.n_y
+    }
+
+    /// The features are the reservoir state after the feature map.
+    fn n_features(&self) -> u64 {
+        self.features.n_features(self.n_x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model_with_feedback() -> EchoStateNetwork {
+        EchoStateNetwork::new(
+            1,
+            1,
+            10,
+            0.2,
+            0.5,
+            0.9,
+            |x| x.tanh(),
+            Some(0.5),
+            None,
+            1.0,
+            |y| y.map(|v| v.tanh()),
+            |y| y.map(|v| v.atanh()),
+            false,
+            ReadoutConfig::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_teacher_output_is_fed_back_in_output_space() {
+        let mut model = model_with_feedback();
+        let teaching_input = vec![vec![0.1], vec![0.2], vec![0.3]];
+        let teaching_output = vec![vec![0.5], vec![-0.5], vec![0.25]];
+
+        model
+            .offline_train(&teaching_input, &teaching_output)
+            .unwrap();
+        assert_eq!(model.previous_y.as_slice(), &[0.25]);
+    }
+
+    #[test]
+    fn test_squared_readout_features() {
+        let mut model = model_with_feedback();
+        model
+            .set_readout_features(ReadoutFeatures::Squared)
+            .unwrap();
+        let teaching_input = vec![vec![0.1], vec![0.2], vec![0.3]];
+        let teaching_output = vec![vec![0.5], vec![-0.5], vec![0.25]];
+
+        let (states, targets) = model
+            .harvest_states(&teaching_input, &teaching_output)
+            .unwrap();
+        assert_eq!(states.nrows(), 20);
+        for n in 0..10 {
+            assert_eq!(states[(10 + n, 0)], states[(n, 0)].powi(2));
+        }
+
+        model.fit_from_states(&states, &targets).unwrap();
+        assert_eq!(model.readout_weight().shape(), (1, 20));
+        assert_eq!(model.estimate(&[0.4]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_minimum_complexity_is_reproducible() {
+        let reservoir =
+            MinimumComplexityReservoir::new(CycleTopology::SimpleCycle { weight: 0.8 }, 0.5);
+        let teaching_input = (0..20)
+            .map(|n| vec![(0.4 * n as f64).sin()])
+            .collect::<Vec<_>>();
+        let teaching_output = teaching_input[1..].to_vec();
+
+        let weights = (0..2)
+            .map(|_| {
+                let mut model = EchoStateNetwork::new_minimum_complexity(
+                    1,
+                    1,
+                    10,
+                    reservoir,
+                    |x| x.tanh(),
+                    1.0,
+                    |y| y.clone_owned(),
+                    |y| y.clone_owned(),
+                    ReadoutConfig::ridge(1e-6),
+                )
+                .unwrap();
+                model
+                    .offline_train(&teaching_input[..19], &teaching_output)
+                    .unwrap();
+                model.readout_weight().clone()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(weights[0], weights[1]);
+    }
+
+    #[test]
+    fn test_train_sequences_matches_sequential_training() {
+        let reservoir =
+            MinimumComplexityReservoir::new(CycleTopology::SimpleCycle { weight: 0.8 }, 0.5);
+        let new_model = || {
+            EchoStateNetwork::new_minimum_complexity(
+                1,
+                1,
+                10,
+                reservoir,
+                |x| x.tanh(),
+                1.0,
+                |y| y.clone_owned(),
+                |y| y.clone_owned(),
+                ReadoutConfig::ridge(1e-6),
+            )
+            .unwrap()
+        };
+        let (inputs, outputs): (Vec<_>, Vec<_>) = [15, 20, 25]
+            .iter()
+            .enumerate()
+            .map(|(k, &length)| {
+                let input = (0..length)
+                    .map(|n| vec![(0.3 * (k + 1) as f64 * n as f64).sin()])
+                    .collect::<Vec<_>>();
+                let output = input.iter().map(|u| vec![u[0].powi(2)]).collect::<Vec<_>>();
+                (input, output)
+            })
+            .unzip();
+
+        let mut parallel = new_model();
+        parallel.offline_train_sequences(&inputs, &outputs).unwrap();
+
+        let mut sequential = new_model();
+        for (input, output) in inputs.iter().zip(outputs.iter()) {
+            sequential.reset();
+            sequential.offline_train(input, output).unwrap();
+        }
+
+        let offline_optimizer = parallel.offline_optimizer.as_ref().unwrap();
+        assert_eq!(offline_optimizer.n_samples(), 60);
+        for (e, a) in sequential
+            .readout_weight()
+            .iter()
+            .zip(parallel.readout_weight().iter())
+        {
+            assert!((e - a).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_to_precision_keeps_optimizers() {
+        let mut model = model_with_feedback();
+        model.set_offline_optimizer(WindowedRidge::sliding_window(10, 1, 0.1, 5).unwrap());
+        let teaching_input = vec![vec![0.1], vec![0.2], vec![0.3]];
+        let teaching_output = vec![vec![0.5], vec![-0.5], vec![0.25]];
+        model
+            .offline_train(&teaching_input, &teaching_output)
+            .unwrap();
+
+        let converted: EchoStateNetwork<f32> = model.to_precision(
+            |x| x.tanh(),
+            |y| y.map(|v| v.tanh()),
+            |y| y.map(|v| v.atanh()),
+        );
+        let offline_optimizer = converted.offline_optimizer().unwrap();
+        assert!(matches!(
+            offline_optimizer,
+            OfflineOptimizer::WindowedRidge(_)
+        ));
+        assert_eq!(offline_optimizer.n_samples(), 3);
+    }
+
+    #[test]
+    fn test_readout_features_keep_optimizers() {
+        let mut model = model_with_feedback();
+        model.set_online_optimizer(LMS::normalized(10, 1, 0.5));
+        model.set_offline_optimizer(ElasticNet::lasso(10, 1, 0.1));
+        model
+            .set_readout_features(ReadoutFeatures::Squared)
+            .unwrap();
+
+        assert!(matches!(
+            model.online_optimizer,
+            Some(OnlineOptimizer::LMS(_))
+        ));
+        let offline_optimizer = model.offline_optimizer().unwrap();
+        assert!(matches!(offline_optimizer, OfflineOptimizer::ElasticNet(_)));
+        assert_eq!(offline_optimizer.shape(), (20, 1));
+
+        let tikhonov = Regularization::Tikhonov(na::DMatrix::identity(20, 20));
+        model.set_offline_optimizer(Ridge::new_with_regularization(20, 1, tikhonov).unwrap());
+        let result = model.set_readout_features(ReadoutFeatures::Linear);
+        assert!(matches!(result, Err(EsnError::Unsupported(_))));
+        assert_eq!(model.n_features(), 20);
+    }
+
+    #[test]
+    fn test_resume_offline_training_with_saved_weights() {
+        let path = std::env::temp_dir().join("esn_test_resume_offline_training_with_weights.json");
+        let path = path.to_str().unwrap();
+        let teaching_input = (0..20)
+            .map(|n| vec![(0.3 * n as f64).sin()])
+            .collect::<Vec<_>>();
+        let teaching_output = (0..20)
+            .map(|n| vec![0.5 * (0.3 * n as f64).cos()])
+            .collect::<Vec<_>>();
+
+        let mut model = model_with_feedback();
+        model
+            .offline_train(&teaching_input[..10], &teaching_output[..10])
+            .unwrap();
+        model.save_offline_optimizer(path).unwrap();
+
+        // The weights of a new network are drawn again and replaced by the saved ones.
+        let mut resumed = model_with_feedback();
+        resumed.load_offline_optimizer(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        model.reset();
+        model
+            .offline_train(&teaching_input[10..], &teaching_output[10..])
+            .unwrap();
+        resumed
+            .offline_train(&teaching_input[10..], &teaching_output[10..])
+            .unwrap();
+        assert_eq!(resumed.offline_optimizer().unwrap().n_samples(), 20);
+        for (r, m) in resumed
+            .readout_weight()
+            .iter()
+            .zip(model.readout_weight().iter())
+        {
+            assert!((r - m).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_scheduled_sampling_waits_for_a_fitted_readout() {
+        let mut model = model_with_feedback();
+        let teaching_input = (0..20).map(|n| vec![0.05 * n as f64]).collect::<Vec<_>>();
+        let teaching_output = (0..20)
+            .map(|n| vec![0.5 * (0.3 * n as f64).sin()])
+            .collect::<Vec<_>>();
+        let harvest = |model: &mut EchoStateNetwork, teacher_forcing| {
+            model.set_teacher_forcing(teacher_forcing).unwrap();
+            model.reset();
+            model
+                .harvest_states(&teaching_input, &teaching_output)
+                .unwrap()
+                .0
+        };
+
+        let forced = harvest(&mut model, TeacherForcing::default());
+        let sampled = harvest(&mut model, TeacherForcing::scheduled_sampling(1.0));
+        assert_eq!(sampled, forced);
+
+        model
+            .offline_train(&teaching_input, &teaching_output)
+            .unwrap();
+        let forced = harvest(&mut model, TeacherForcing::default());
+        let sampled = harvest(&mut model, TeacherForcing::scheduled_sampling(1.0));
+        assert_ne!(sampled, forced);
     }
 }
@@ -0,0 +1,351 @@
+use nalgebra as na;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Parameters of the leaky integrate-and-fire neurons of a `LiquidStateMachine`.
+/// The times are in the same unit, e.g. milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LifParameters {
+    /// Simulation time step
+    pub dt: f64,
+    /// Time constant of the membrane potential
+    pub membrane_time_constant: f64,
+    /// Time constant of the exponential decay of the synaptic currents
+    pub synaptic_time_constant: f64,
+    /// Time constant of the filter of the spike counts read by the readout
+    pub readout_time_constant: f64,
+    /// Membrane potential at which a neuron spikes
+    pub threshold: f64,
+    /// Membrane potential after a spike and at rest
+    pub reset_potential: f64,
+    /// Time after a spike during which a neuron ignores its inputs
+    pub refractory_period: f64,
+}
+
+impl Default for LifParameters {
+    fn default() -> Self {
+        LifParameters {
+            dt: 1.0,
+            membrane_time_constant: 20.0,
+            synaptic_time_constant: 5.0,
+            readout_time_constant: 20.0,
+            threshold: 1.0,
+            reset_potential: 0.0,
+            refractory_period: 2.0,
+        }
+    }
+}
+
+/// How an input sample, normalized to [0, 1], is encoded into spike trains over the simulation steps of one sample.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SpikeEncoding {
+    /// Rate coding: each input spikes at each step with a probability of the input times 'max_probability'.
+    Rate { max_probability: f64 },
+    /// Temporal (latency) coding: each positive input spikes once, earlier for larger inputs.
+    Temporal,
+}
+
+impl SpikeEncoding {
+    /// Return the input spikes, one step per column (n_u x n_steps).
+    fn encode<T: Float>(&self, u: &[T], n_steps: usize) -> na::DMatrix<T> {
+        let mut spikes = na::DMatrix::zeros(u.len(), n_steps);
+        match self {
+            SpikeEncoding::Rate { max_probability } => {
+                let mut rng = thread_rng();
+                for (i, &u) in u.iter().enumerate() {
+                    let probability = (to_f64(u) * max_probability).clamp(0.0, 1.0);
+                    for step in 0..n_steps {
+                        if rng.gen_bool(probability) {
+                            spikes[(i, step)] = T::one();
+                        }
+                    }
+                }
+            }
+            SpikeEncoding::Temporal => {
+                for (i, &u) in u.iter().enumerate() {
+                    let u = to_f64(u);
+                    if u > 0.0 {
+                        let step = ((1.0 - u.min(1.0)) * (n_steps - 1) as f64).round() as usize;
+                        spikes[(i, step)] = T::one();
+                    }
+                }
+            }
+        }
+        spikes
+    }
+}
+
+/// Liquid state machine, a reservoir of spiking leaky integrate-and-fire neurons.
+/// Each input sample is encoded into spike trains and presented for 'n_steps' simulation steps,
+/// then the readout is applied to the filtered spike counts of the neurons.
+pub struct LiquidStateMachine<T = f64> {
+    input: Input<T>,
+    reservoir: SpikingReservoir<T>,
+    output: Output<T>,
+    encoding: SpikeEncoding,
+    n_steps: usize,
+    n_u: u64,
+    n_y: u64,
+    n_x: u64,
+    online_optimizer: Option<OnlineOptimizer<T>>,
+    offline_optimizer: Option<OfflineOptimizer<T>>,
+}
+
+impl LiquidStateMachine {
+    /// Create a new LiquidStateMachine.
+    /// n_u: The number of input variables.
+    /// n_y: The number of output variables.
+    /// n_x: The number of neurons.
+    /// density: The connection density of the neurons.
+    /// input_scale: The scale of the synaptic weights of the input spikes.
+    /// rho: The spectral radius of the recurrent synaptic weights.
+    /// lif: The parameters of the neurons.
+    /// encoding: The encoding of the inputs into spike trains.
+    /// n_steps: The number of simulation steps for each sample.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        n_u: u64,
+        n_y: u64,
+        n_x: u64,
+        density: f64,
+        input_scale: f64,
+        rho: f64,
+        lif: LifParameters,
+        encoding: SpikeEncoding,
+        n_steps: usize,
+        readout: ReadoutConfig,
+    ) -> Result<Self> {
+        Self::new_with_precision(
+            n_u,
+            n_y,
+            n_x,
+            density,
+            input_scale,
+            rho,
+            lif,
+            encoding,
+            n_steps,
+            readout,
+        )
+    }
+}
+
+impl<T: Float> LiquidStateMachine<T> {
+    /// Create a new LiquidStateMachine computing in the floating-point type 'T'.
+    /// The parameters are the same as `new`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_precision(
+        n_u: u64,
+        n_y: u64,
+        n_x: u64,
+        density: f64,
+        input_scale: f64,
+        rho: f64,
+        lif: LifParameters,
+        encoding: SpikeEncoding,
+        n_steps: usize,
+        readout: ReadoutConfig,
+    ) -> Result<Self> {
+        if n_steps == 0 {
+            return Err(EsnError::InvalidParameter(
+                "The number of simulation steps must be positive".to_string(),
+            ));
+        }
+
+        Ok(LiquidStateMachine {
+            input: Input::new(n_u, n_x, input_scale),
+            reservoir: SpikingReservoir::new(n_x, density, rho, &lif, None),
+            output: Output::new(n_y, n_x),
+            encoding,
+            n_steps,
+            n_u,
+            n_y,
+            n_x,
+            online_optimizer: Some(readout.online.build(n_x, n_y)?),
+            offline_optimizer: Some(readout.offline.build(n_x, n_y)?),
+        })
+    }
+
+    pub fn readout_weight(&self) -> &na::DMatrix<T> {
+        self.output.output_weight()
+    }
+
+    /// Replace both optimizers with new ones built from the configuration.
+    pub fn set_readout_config(&mut self, readout: ReadoutConfig) -> Result<()> {
+        let online_optimizer = readout.online.build(self.n_x, self.n_y)?;
+        let offline_optimizer = readout.offline.build(self.n_x, self.n_y)?;
+        self.online_optimizer = Some(online_optimizer);
+        self.offline_optimizer = Some(offline_optimizer);
+        Ok(())
+    }
+
+    /// Return the optimizer used by the offline training and its accumulated data.
+    pub fn offline_optimizer(&self) -> Option<&OfflineOptimizer<T>> {
+        self.offline_optimizer.as_ref()
+    }
+
+    /// Present one input sample to the neurons and return the filtered spike counts at the end.
+    fn call(&mut self, u: &[T]) -> na::DVector<T> {
+        let spikes = self.encoding.encode(u, self.n_steps);
+        let mut trace = na::DVector::zeros(self.reservoir.n_x());
+        for step in spikes.column_iter() {
+            let x_in = self.input.call(&step.clone_owned());
+            trace = self.reservoir.call(&x_in);
+        }
+        trace
+    }
+}
+
+impl<T: Float> ReservoirComputing<T> for LiquidStateMachine<T> {
+    /// Online training method.
+    /// teaching_input: The filtered spike counts, e.g. harvested by `harvest_states`.
+    fn train(&mut self, teaching_input: &[T], teaching_output: &[T]) -> Result<()> {
+        check_sample(0, teaching_input, self.n_x as usize)?;
+        check_sample(0, teaching_output, self.n_y as usize)?;
+        let optimizer = self
+            .online_optimizer
+            .as_mut()
+            .ok_or(EsnError::NotInitialized("online optimizer"))?;
+        let x = na::DVector::from_vec(teaching_input.to_vec());
+        let d = na::DVector::from_vec(teaching_output.to_vec());
+        optimizer.set_data(&x, &d)?;
+        self.output.set_weight(optimizer.fit()?);
+        self.output.set_link(optimizer.link());
+
+        Ok(())
+    }
+
+    fn estimate(&mut self, input: &[T]) -> Result<Vec<T>> {
+        check_sample(0, input, self.n_u as usize)?;
+        let x = self.call(input);
+        Ok(self.output.call(&x).as_slice().to_vec())
+    }
+
+    /// The states are the filtered spike counts at the end of each sample.
+    fn harvest_states(
+        &mut self,
+        teaching_input: &[Vec<T>],
+        teaching_output: &[Vec<T>],
+    ) -> Result<(na::DMatrix<T>, na::DMatrix<T>)> {
+        check_dimension(teaching_input.len(), teaching_output.len())?;
+        check_samples(teaching_input, self.n_u as usize)?;
+        check_samples(teaching_output, self.n_y as usize)?;
+
+        let mut states = na::DMatrix::zeros(self.n_x as usize, teaching_input.len());
+        for (n, input) in teaching_input.iter().enumerate() {
+            states.set_column(n, &self.call(input));
+        }
+        let targets = na::DMatrix::from_fn(self.n_y as usize, teaching_output.len(), |i, n| {
+            teaching_output[n][i]
+        });
+
+        Ok((states, targets))
+    }
+
+    fn fit_from_states(&mut self, states: &na::DMatrix<T>, targets: &na::DMatrix<T>) -> Result<()> {
+        let optimizer = self
+            .offline_optimizer
+            .as_mut()
+            .ok_or(EsnError::NotInitialized("offline optimizer"))?;
+        optimizer.set_batch(states, targets)?;
+        self.output.set_weight(optimizer.fit()?);
+        self.output.set_link(optimizer.link());
+
+        Ok(())
+    }
+
+    /// Reset the neurons to their resting state.
+    fn reset(&mut self) {
+        self.reservoir.reset();
+    }
+
+    fn n_inputs(&self) -> u64 {
+        self.n_u
+    }
+
+    fn n_outputs(&self) -> u64 {
+        self.n_y
+    }
+
+    fn n_features(&self) -> u64 {
+        self.n_x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temporal_encoding() {
+        let spikes = SpikeEncoding::Temporal.encode(&[1.0, 0.5, 0.0], 5);
+        assert_eq!(spikes[(0, 0)], 1.0);
+        assert_eq!(spikes.row(0).iter().sum::<f64>(), 1.0);
+        assert_eq!(spikes.row(1).iter().sum::<f64>(), 1.0);
+        assert_eq!(spikes[(1, 2)], 1.0);
+        assert_eq!(spikes.row(2).iter().sum::<f64>(), 0.0);
+    }
+
+    #[test]
+    fn test_refractory_period_limits_the_firing_rate() {
+        let lif = LifParameters {
+            refractory_period: 4.0,
+            ..Default::default()
+        };
+        let mut reservoir = SpikingReservoir::new(5, 0.2, 0.9, &lif, None);
+        let x_in = na::DVector::from_element(5, 100.0);
+
+        let trace_decay = (-lif.dt / lif.readout_time_constant).exp();
+        let mut n_spikes = 0.0;
+        let mut previous = na::DVector::zeros(5);
+        for _ in 0..50 {
+            let trace = reservoir.call(&x_in);
+            n_spikes += trace[0] - previous[0] * trace_decay;
+            previous = trace;
+        }
+        // A spike every refractory period + 1 steps at most
+        assert!((n_spikes - 10.0_f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_membrane_rests_at_the_reset_potential() {
+        let lif = LifParameters {
+            reset_potential: -0.5,
+            ..Default::default()
+        };
+        // Inputs giving steady synaptic currents of 1.2 and 1.6
+        let synapse_decay = (-lif.dt / lif.synaptic_time_constant).exp();
+        let current = |i: f64| na::DVector::from_element(5, i * (1.0 - synapse_decay));
+
+        // The membrane settles at -0.5 + 1.2 = 0.7, below the threshold.
+        let mut reservoir = SpikingReservoir::new(5, 0.2, 0.9, &lif, None);
+        let below = current(1.2);
+        for _ in 0..200 {
+            assert_eq!(reservoir.call(&below).sum(), 0.0);
+        }
+
+        reservoir.reset();
+        let above = current(1.6);
+        let traces = (0..200).map(|_| reservoir.call(&above)).collect::<Vec<_>>();
+        assert!(traces[199].sum() > 0.0);
+    }
+
+    #[test]
+    fn test_zero_steps() {
+        let result = LiquidStateMachine::new(
+            1,
+            1,
+            10,
+            0.2,
+            1.0,
+            0.9,
+            LifParameters::default(),
+            SpikeEncoding::Temporal,
+            0,
+            ReadoutConfig::default(),
+        );
+        assert!(matches!(result, Err(EsnError::InvalidParameter(_))));
+    }
+}
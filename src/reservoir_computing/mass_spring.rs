@@ -0,0 +1,390 @@
+use nalgebra as na;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{EsnError, Float, Result, cast, check_sample};
+
+/// Sensor readings of a `MassSpringNetwork`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MassSpringSensor {
+    /// The length of each spring
+    SpringLengths,
+    /// The x and y positions of each node
+    NodePositions,
+}
+
+/// Parameters of a `MassSpringNetwork`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MassSpringParameters {
+    /// Mass of each node
+    pub mass: f64,
+    /// Linear coefficient k1 of the spring force k1 Δ + k3 Δ³ for an elongation Δ
+    pub linear_stiffness: f64,
+    /// Cubic coefficient k3 of the spring force
+    pub cubic_stiffness: f64,
+    /// Damping coefficient on the relative velocity along each spring
+    pub damping: f64,
+    /// Scale of the random weights of the input forces
+    pub input_scale: f64,
+    /// The number of nearest nodes each node is connected to.
+    /// With too few springs, the network is not rigid and drifts.
+    pub neighbours: usize,
+    /// Integration time step
+    pub dt: f64,
+    /// The number of integration steps for each input sample
+    pub steps_per_sample: usize,
+}
+
+impl Default for MassSpringParameters {
+    fn default() -> Self {
+        MassSpringParameters {
+            mass: 1.0,
+            linear_stiffness: 50.0,
+            cubic_stiffness: 200.0,
+            damping: 5.0,
+            input_scale: 10.0,
+            neighbours: 8,
+            dt: 0.001,
+            steps_per_sample: 100,
+        }
+    }
+}
+
+/// Simulator of a physical reservoir made of a random 2-D network of masses connected by nonlinear spring-dampers.
+/// The nodes are placed at random in the unit square, and each node is connected to its nearest nodes.
+/// The inputs are forces on the input nodes, while the fixed nodes anchor the network.
+/// The equations of motion are integrated by the Runge-Kutta method of order 4,
+/// and the sensor readings are the sensor data of a `PhysicalReservoir` readout.
+pub struct MassSpringNetwork<T = f64> {
+    parameters: MassSpringParameters,
+    sensor: MassSpringSensor,
+    /// Springs as pairs of nodes with their rest length
+    springs: Vec<(usize, usize, T)>,
+    /// Input nodes with the weights of the inputs in the x and y directions (2 x n_u)
+    input_nodes: Vec<(usize, na::DMatrix<T>)>,
+    is_fixed: Vec<bool>,
+    rest_positions: Vec<na::Vector2<T>>,
+    positions: Vec<na::Vector2<T>>,
+    velocities: Vec<na::Vector2<T>>,
+    n_u: usize,
+}
+
+impl MassSpringNetwork {
+    /// Create a new MassSpringNetwork at rest.
+    /// n_u: The number of input variables.
+    /// n_nodes: The number of masses.
+    /// input_nodes: The nodes driven by the input forces.
+    /// fixed_nodes: The nodes which do not move.
+    /// seed: The seed of the node positions and of the input weights.
+    pub fn new(
+        n_u: u64,
+        n_nodes: u64,
+        input_nodes: &[usize],
+        fixed_nodes: &[usize],
+        sensor: MassSpringSensor,
+        parameters: MassSpringParameters,
+        seed: Option<u64>,
+    ) -> Result<Self> {
+        Self::new_with_precision(
+            n_u,
+            n_nodes,
+            input_nodes,
+            fixed_nodes,
+            sensor,
+            parameters,
+            seed,
+        )
+    }
+}
+
+impl<T: Float> MassSpringNetwork<T> {
+    /// Create a new MassSpringNetwork computing in the floating-point type 'T',
+    /// e.g. to feed a `PhysicalReservoir<f32>`. The parameters are the same as `new`.
+    pub fn new_with_precision(
+        n_u: u64,
+        n_nodes: u64,
+        input_nodes: &[usize],
+        fixed_nodes: &[usize],
+        sensor: MassSpringSensor,
+        parameters: MassSpringParameters,
+        seed: Option<u64>,
+    ) -> Result<Self> {
+        let n_nodes = n_nodes as usize;
+        if input_nodes.iter().chain(fixed_nodes).any(|&i| i >= n_nodes) {
+            return Err(EsnError::InvalidParameter(
+                "The input and fixed nodes must be nodes of the network".to_string(),
+            ));
+        }
+        if parameters.steps_per_sample == 0 {
+            return Err(EsnError::InvalidParameter(
+                "The number of integration steps must be positive".to_string(),
+            ));
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed.unwrap_or(0));
+        let rest_positions = (0..n_nodes)
+            .map(|_| na::Vector2::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)))
+            .collect::<Vec<na::Vector2<f64>>>();
+        let rest_positions = rest_positions
+            .iter()
+            .map(|p| p.map(cast))
+            .collect::<Vec<na::Vector2<T>>>();
+
+        let mut springs = vec![];
+        for (i, p) in rest_positions.iter().enumerate() {
+            let mut nearest = (0..n_nodes).filter(|&j| j != i).collect::<Vec<_>>();
+            nearest.sort_by(|&a, &b| {
+                (rest_positions[a] - p)
+                    .norm()
+                    .partial_cmp(&(rest_positions[b] - p).norm())
+                    .unwrap()
+            });
+            for &j in nearest.iter().take(parameters.neighbours) {
+                let pair = (i.min(j), i.max(j));
+                if !springs.iter().any(|&(a, b, _)| (a, b) == pair) {
+                    springs.push((pair.0, pair.1, (rest_positions[j] - p).norm()));
+                }
+            }
+        }
+
+        let input_nodes = input_nodes
+            .iter()
+            .map(|&i| {
+                let weight = na::DMatrix::from_fn(2, n_u as usize, |_, _| {
+                    cast(rng.gen_range(-1.0..1.0) * parameters.input_scale)
+                });
+                (i, weight)
+            })
+            .collect();
+
+        let mut is_fixed = vec![false; n_nodes];
+        for &i in fixed_nodes {
+            is_fixed[i] = true;
+        }
+
+        Ok(MassSpringNetwork {
+            parameters,
+            sensor,
+            springs,
+            input_nodes,
+            is_fixed,
+            positions: rest_positions.clone(),
+            rest_positions,
+            velocities: vec![na::Vector2::zeros(); n_nodes],
+            n_u: n_u as usize,
+        })
+    }
+
+    /// Apply the input forces for one sample and return the sensor readings at the end.
+    pub fn call(&mut self, input: &[T]) -> Result<Vec<T>> {
+        check_sample(0, input, self.n_u)?;
+        let u = na::DVector::from_column_slice(input);
+        let mut external = vec![na::Vector2::zeros(); self.positions.len()];
+        for (i, weight) in self.input_nodes.iter() {
+            external[*i] += na::Vector2::from_iterator((weight * &u).iter().cloned());
+        }
+
+        let dt = cast::<T>(self.parameters.dt);
+        let (half, two, six) = (cast::<T>(0.5), cast::<T>(2.0), cast::<T>(6.0));
+        for _ in 0..self.parameters.steps_per_sample {
+            let (p, v) = (&self.positions, &self.velocities);
+            let (dp1, dv1) = self.derivative(p, v, &external);
+            let (dp2, dv2) = self.derivative(
+                &add(p, &dp1, half * dt),
+                &add(v, &dv1, half * dt),
+                &external,
+            );
+            let (dp3, dv3) = self.derivative(
+                &add(p, &dp2, half * dt),
+                &add(v, &dv2, half * dt),
+                &external,
+            );
+            let (dp4, dv4) = self.derivative(&add(p, &dp3, dt), &add(v, &dv3, dt), &external);
+
+            for i in 0..self.positions.len() {
+                self.positions[i] += (dp1[i] + dp2[i] * two + dp3[i] * two + dp4[i]) * (dt / six);
+                self.velocities[i] += (dv1[i] + dv2[i] * two + dv3[i] * two + dv4[i]) * (dt / six);
+            }
+        }
+
+        Ok(self.sensor_readings())
+    }
+
+    /// Run the network on a sequence of input samples, and return the sensor readings for each sample.
+    pub fn run(&mut self, inputs: &[Vec<T>]) -> Result<Vec<Vec<T>>> {
+        inputs.iter().map(|input| self.call(input)).collect()
+    }
+
+    /// Put the network back at rest.
+    pub fn reset(&mut self) {
+        self.positions.clone_from(&self.rest_positions);
+        self.velocities.fill(na::Vector2::zeros());
+    }
+
+    /// Return the number of sensor readings, i.e. the 'n_x' of the `PhysicalReservoir` readout.
+    pub fn n_sensors(&self) -> u64 {
+        match self.sensor {
+            MassSpringSensor::SpringLengths => self.springs.len() as u64,
+            MassSpringSensor::NodePositions => 2 * self.positions.len() as u64,
+        }
+    }
+
+    pub fn positions(&self) -> &[na::Vector2<T>] {
+        &self.positions
+    }
+
+    fn sensor_readings(&self) -> Vec<T> {
+        match self.sensor {
+            MassSpringSensor::SpringLengths => self
+                .springs
+                .iter()
+                .map(|&(a, b, _)| (self.positions[b] - self.positions[a]).norm())
+                .collect(),
+            MassSpringSensor::NodePositions => {
+                self.positions.iter().flat_map(|p| [p.x, p.y]).collect()
+            }
+        }
+    }
+
+    /// Return the derivatives of the positions and of the velocities.
+    fn derivative(
+        &self,
+        positions: &[na::Vector2<T>],
+        velocities: &[na::Vector2<T>],
+        external: &[na::Vector2<T>],
+    ) -> (Vec<na::Vector2<T>>, Vec<na::Vector2<T>>) {
+        let MassSpringParameters {
+            mass,
+            linear_stiffness,
+            cubic_stiffness,
+            damping,
+            ..
+        } = self.parameters;
+        let (mass, linear_stiffness, cubic_stiffness, damping) = (
+            cast::<T>(mass),
+            cast::<T>(linear_stiffness),
+            cast::<T>(cubic_stiffness),
+            cast::<T>(damping),
+        );
+
+        let mut forces = external.to_vec();
+        for &(a, b, rest_length) in self.springs.iter() {
+            let d = positions[b] - positions[a];
+            let length = d.norm();
+            if length == T::zero() {
+                continue;
+            }
+            let direction = d / length;
+            let elongation = length - rest_length;
+            let relative_velocity = (velocities[b] - velocities[a]).dot(&direction);
+            let force = direction
+                * (linear_stiffness * elongation
+                    + cubic_stiffness * elongation.powi(3)
+                    + damping * relative_velocity);
+            forces[a] += force;
+            forces[b] -= force;
+        }
+
+        let mut dp = velocities.to_vec();
+        let mut dv = forces.iter().map(|f| f / mass).collect::<Vec<_>>();
+        for (i, &is_fixed) in self.is_fixed.iter().enumerate() {
+            if is_fixed {
+                dp[i] = na::Vector2::zeros();
+                dv[i] = na::Vector2::zeros();
+            }
+        }
+        (dp, dv)
+    }
+}
+
+/// Return x + scale * dx for each node.
+fn add<T: Float>(x: &[na::Vector2<T>], dx: &[na::Vector2<T>], scale: T) -> Vec<na::Vector2<T>> {
+    x.iter()
+        .zip(dx.iter())
+        .map(|(x, dx)| x + dx * scale)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network() -> MassSpringNetwork {
+        MassSpringNetwork::new(
+            1,
+            10,
+            &[0, 1],
+            &[2, 3],
+            MassSpringSensor::SpringLengths,
+            MassSpringParameters {
+                steps_per_sample: 10,
+                ..Default::default()
+            },
+            Some(3),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rest_without_input() {
+        let mut network = network();
+        let rest_lengths = network.springs.iter().map(|s| s.2).collect::<Vec<_>>();
+
+        let readings = network.run(&vec![vec![0.0]; 10]).unwrap();
+        assert_eq!(readings[0].len() as u64, network.n_sensors());
+        for (reading, rest_length) in readings[9].iter().zip(rest_lengths.iter()) {
+            assert!((reading - rest_length).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_motion_is_damped_after_a_pulse() {
+        let mut network = network();
+        let rest_lengths = network.springs.iter().map(|s| s.2).collect::<Vec<_>>();
+        let deviation = |readings: &[f64]| {
+            readings
+                .iter()
+                .zip(rest_lengths.iter())
+                .map(|(r, l)| (r - l).abs())
+                .fold(0.0, f64::max)
+        };
+
+        let driven = network.run(&vec![vec![5.0]; 20]).unwrap();
+        let released = network.run(&vec![vec![0.0]; 300]).unwrap();
+        assert!(deviation(&driven[19]) > 1e-3);
+        assert!(deviation(&released[299]) < 0.1 * deviation(&driven[19]));
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let new_network = |input_nodes: &[usize], parameters| {
+            MassSpringNetwork::new(
+                1,
+                10,
+                input_nodes,
+                &[2, 3],
+                MassSpringSensor::SpringLengths,
+                parameters,
+                None,
+            )
+        };
+        let zero_steps = MassSpringParameters {
+            steps_per_sample: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            new_network(&[10], MassSpringParameters::default()),
+            Err(EsnError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            new_network(&[0], zero_steps),
+            Err(EsnError::InvalidParameter(_))
+        ));
+
+        let without_input = MassSpringParameters {
+            input_scale: 0.0,
+            ..Default::default()
+        };
+        assert!(new_network(&[0], without_input).is_ok());
+    }
+}
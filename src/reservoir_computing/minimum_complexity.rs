@@ -0,0 +1,187 @@
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::{EsnError, Result};
+
+/// Deterministic topology of a minimum-complexity reservoir, with shared weight values.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CycleTopology {
+    /// Simple cycle reservoir: a unidirectional ring of nodes connected by 'weight'.
+    SimpleCycle { weight: f64 },
+    /// Delay line with feedback: a chain of nodes connected forward by 'weight' and backward by 'feedback'.
+    DelayLineFeedback { weight: f64, feedback: f64 },
+    /// Cycle reservoir with regular jumps: a simple cycle with bidirectional 'jump_weight' connections
+    /// between every 'jump'-th node.
+    CycleJumps {
+        weight: f64,
+        jump_weight: f64,
+        jump: usize,
+    },
+}
+
+/// Minimum-complexity reservoir, built without randomness from a handful of parameters.
+/// The input weights all have the absolute value 'input_scale',
+/// with signs following the digits of π (negative for 0 to 4, positive for 5 to 9).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MinimumComplexityReservoir {
+    pub topology: CycleTopology,
+    pub input_scale: f64,
+}
+
+impl MinimumComplexityReservoir {
+    pub fn new(topology: CycleTopology, input_scale: f64) -> Self {
+        MinimumComplexityReservoir {
+            topology,
+            input_scale,
+        }
+    }
+
+    /// Return the reservoir weight of 'n_x' nodes (n_x x n_x).
+    /// The jump of `CycleJumps` must be in [1, n_x), so that it does not connect a node to itself.
+    pub fn reservoir_weight(&self, n_x: u64) -> Result<na::DMatrix<f64>> {
+        let n_x = n_x as usize;
+        let mut weight = na::DMatrix::zeros(n_x, n_x);
+        match self.topology {
+            CycleTopology::SimpleCycle { weight: r } => {
+                for i in 0..n_x {
+                    weight[((i + 1) % n_x, i)] = r;
+                }
+            }
+            CycleTopology::DelayLineFeedback {
+                weight: r,
+                feedback: b,
+            } => {
+                for i in 0..n_x.saturating_sub(1) {
+                    weight[(i + 1, i)] = r;
+                    weight[(i, i + 1)] = b;
+                }
+            }
+            CycleTopology::CycleJumps {
+                weight: r,
+                jump_weight,
+                jump,
+            } => {
+                if jump == 0 || jump >= n_x {
+                    return Err(EsnError::InvalidParameter(
+                        "The jump size must be in [1, n_x)".to_string(),
+                    ));
+                }
+                for i in 0..n_x {
+                    weight[((i + 1) % n_x, i)] = r;
+                }
+                for i in (0..n_x - n_x % jump).step_by(jump) {
+                    let j = (i + jump) % n_x;
+                    weight[(i, j)] = jump_weight;
+                    weight[(j, i)] = jump_weight;
+                }
+            }
+        }
+        Ok(weight)
+    }
+
+    /// Return the input weight of 'n_u' inputs to 'n_x' nodes (n_x x n_u).
+    pub fn input_weight(&self, n_u: u64, n_x: u64) -> na::DMatrix<f64> {
+        let digits = pi_digits((n_u * n_x) as usize);
+        na::DMatrix::from_fn(n_x as usize, n_u as usize, |i, j| {
+            if digits[i * n_u as usize + j] < 5 {
+                -self.input_scale
+            } else {
+                self.input_scale
+            }
+        })
+    }
+}
+
+/// Return the first 'n' decimal digits of π, starting with 3, by the spigot algorithm of Rabinowitz and Wagon.
+fn pi_digits(n: usize) -> Vec<u8> {
+    // A few more digits than needed, since the last ones may still be held back.
+    let n_computed = n + 10;
+    let len = 10 * n_computed / 3 + 1;
+    let mut remainders = vec![2u64; len];
+    let mut digits = Vec::with_capacity(n_computed);
+    let mut predigit: Option<u8> = None;
+    let mut nines = 0;
+
+    for _ in 0..n_computed {
+        let mut carry = 0;
+        for i in (1..=len).rev() {
+            let x = 10 * remainders[i - 1] + carry * i as u64;
+            let denominator = 2 * i as u64 - 1;
+            remainders[i - 1] = x % denominator;
+            carry = x / denominator;
+        }
+        remainders[0] = carry % 10;
+        let q = (carry / 10) as u8;
+
+        match q {
+            9 => nines += 1,
+            10 => {
+                digits.push(predigit.map_or(1, |p| p + 1));
+                digits.extend(std::iter::repeat_n(0, nines));
+                predigit = Some(0);
+                nines = 0;
+            }
+            _ => {
+                digits.extend(predigit);
+                digits.extend(std::iter::repeat_n(9, nines));
+                predigit = Some(q);
+                nines = 0;
+            }
+        }
+    }
+
+    digits.truncate(n);
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pi_digits() {
+        assert_eq!(
+            pi_digits(20),
+            vec![3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5, 8, 9, 7, 9, 3, 2, 3, 8, 4]
+        );
+        // The six nines from the 763rd digit
+        assert_eq!(&pi_digits(768)[762..768], &[9; 6]);
+    }
+
+    #[test]
+    fn test_weights() {
+        let reservoir = MinimumComplexityReservoir::new(
+            CycleTopology::CycleJumps {
+                weight: 0.5,
+                jump_weight: 0.2,
+                jump: 2,
+            },
+            0.1,
+        );
+        let weight = reservoir.reservoir_weight(4).unwrap();
+        assert_eq!(weight[(1, 0)], 0.5);
+        assert_eq!(weight[(0, 3)], 0.5);
+        assert_eq!(weight[(0, 2)], 0.2);
+        assert_eq!(weight[(2, 0)], 0.2);
+        assert_eq!(weight.iter().filter(|&&w| w != 0.0).count(), 6);
+
+        let input_weight = reservoir.input_weight(1, 4);
+        assert_eq!(input_weight.as_slice(), &[-0.1, -0.1, -0.1, -0.1]);
+        assert_eq!(reservoir.input_weight(1, 6)[(5, 0)], 0.1);
+
+        for jump in [0, 4] {
+            let reservoir = MinimumComplexityReservoir::new(
+                CycleTopology::CycleJumps {
+                    weight: 0.5,
+                    jump_weight: 0.2,
+                    jump,
+                },
+                0.1,
+            );
+            assert!(matches!(
+                reservoir.reservoir_weight(4),
+                Err(EsnError::InvalidParameter(_))
+            ));
+        }
+    }
+}
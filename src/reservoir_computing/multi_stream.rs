@@ -0,0 +1,217 @@
+use nalgebra as na;
+
+use crate::*;
+
+/// Identifier of a stream of a `MultiStreamEchoStateNetwork`.
+pub type StreamId = u64;
+
+/// Echo state network running many independent input streams on one shared reservoir.
+/// The states of the B streams are the columns of an n_x x B matrix,
+/// so that all the streams advance in one matrix-matrix product per step.
+/// It is created from a trained `EchoStateNetwork` by `EchoStateNetwork::multi_stream`.
+pub struct MultiStreamEchoStateNetwork<T = f64> {
+    input: Input<T>,
+    reservoir: Reservoir<T>,
+    output: Output<T>,
+    feedback: Option<Feedback<T>>,
+    features: ReadoutFeatures,
+    output_function: fn(&na::DVector<T>) -> na::DVector<T>,
+    n_u: u64,
+    n_y: u64,
+    /// Stream identifiers, in the order of the columns of the states
+    streams: Vec<StreamId>,
+    /// Reservoir states, one stream per column
+    states: na::DMatrix<T>,
+    /// Last outputs, one stream per column
+    previous_y: na::DMatrix<T>,
+}
+
+impl<T: Float> MultiStreamEchoStateNetwork<T> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        input: Input<T>,
+        reservoir: Reservoir<T>,
+        output: Output<T>,
+        feedback: Option<Feedback<T>>,
+        features: ReadoutFeatures,
+        output_function: fn(&na::DVector<T>) -> na::DVector<T>,
+        n_u: u64,
+        n_y: u64,
+    ) -> Self {
+        let n_x = reservoir.n_x();
+        MultiStreamEchoStateNetwork {
+            input,
+            reservoir,
+            output,
+            feedback,
+            features,
+            output_function,
+            n_u,
+            n_y,
+            streams: vec![],
+            states: na::DMatrix::zeros(n_x, 0),
+            previous_y: na::DMatrix::zeros(n_y as usize, 0),
+        }
+    }
+
+    /// Add a stream starting from a zero state.
+    pub fn add_stream(&mut self, id: StreamId) -> Result<()> {
+        if self.streams.contains(&id) {
+            return Err(EsnError::InvalidParameter(format!(
+                "Stream {} already exists",
+                id
+            )));
+        }
+        let n = self.streams.len();
+        self.streams.push(id);
+        self.states = std::mem::take(&mut self.states).insert_column(n, T::zero());
+        self.previous_y = std::mem::take(&mut self.previous_y).insert_column(n, T::zero());
+        Ok(())
+    }
+
+    /// Remove a stream and its state.
+    pub fn remove_stream(&mut self, id: StreamId) -> Result<()> {
+        let n = self.stream_index(id)?;
+        self.streams.remove(n);
+        self.states = std::mem::take(&mut self.states).remove_column(n);
+        self.previous_y = std::mem::take(&mut self.previous_y).remove_column(n);
+        Ok(())
+    }
+
+    /// Reset the state of one stream to zero, leaving the other streams untouched.
+    pub fn reset_stream(&mut self, id: StreamId) -> Result<()> {
+        let n = self.stream_index(id)?;
+        self.states.column_mut(n).fill(T::zero());
+        self.previous_y.column_mut(n).fill(T::zero());
+        Ok(())
+    }
+
+    /// Reset the states of all the streams to zero.
+    pub fn reset(&mut self) {
+        self.states.fill(T::zero());
+        self.previous_y.fill(T::zero());
+    }
+
+    /// Return the stream identifiers, in the order of the columns of `step`.
+    pub fn stream_ids(&self) -> &[StreamId] {
+        &self.streams
+    }
+
+    pub fn n_streams(&self) -> usize {
+        self.streams.len()
+    }
+
+    pub fn n_outputs(&self) -> u64 {
+        self.n_y
+    }
+
+    /// Return the reservoir state of a stream.
+    pub fn state(&self, id: StreamId) -> Result<na::DVector<T>> {
+        let n = self.stream_index(id)?;
+        Ok(self.states.column(n).clone_owned())
+    }
+
+    /// Return the last output of a stream.
+    pub fn last_output(&self, id: StreamId) -> Result<Vec<T>> {
+        let n = self.stream_index(id)?;
+        Ok(self.previous_y.column(n).iter().cloned().collect())
+    }
+
+    /// Advance all the streams by one step.
+    /// inputs: One input per column (n_u x B), in the order of `stream_ids`.
+    /// Return the outputs, one per column (n_y x B).
+    pub fn step(&mut self, inputs: &na::DMatrix<T>) -> Result<na::DMatrix<T>> {
+        if inputs.shape() != (self.n_u as usize, self.streams.len()) {
+            return Err(EsnError::ShapeMismatch {
+                expected: (self.n_u as usize, self.streams.len()),
+                actual: inputs.shape(),
+            });
+        }
+
+        let mut x_in = self.input.call_batch(inputs);
+        if let Some(fdb) = self.feedback.as_ref() {
+            x_in += fdb.give_feedback_batch(&self.previous_y);
+        }
+
+        self.states = self.reservoir.call_batch(&self.states, &x_in);
+
+        let mut y = self
+            .output
+            .call_batch(&self.features.apply_batch(&self.states));
+        for mut column in y.column_iter_mut() {
+            let transformed = (self.output_function)(&column.clone_owned());
+            column.copy_from(&transformed);
+        }
+
+        self.previous_y = y.clone();
+
+        Ok(y)
+    }
+
+    /// Advance all the streams by one step, with one input sample per stream in the order of `stream_ids`.
+    pub fn step_samples(&mut self, inputs: &[Vec<T>]) -> Result<Vec<Vec<T>>> {
+        check_dimension(self.streams.len(), inputs.len())?;
+        check_samples(inputs, self.n_u as usize)?;
+        let inputs = na::DMatrix::from_fn(self.n_u as usize, inputs.len(), |i, n| inputs[n][i]);
+        let outputs = self.step(&inputs)?;
+        Ok(outputs
+            .column_iter()
+            .map(|y| y.iter().cloned().collect())
+            .collect())
+    }
+
+    fn stream_index(&self, id: StreamId) -> Result<usize> {
+        self.streams
+            .iter()
+            .position(|stream| *stream == id)
+            .ok_or_else(|| EsnError::InvalidParameter(format!("Stream {} does not exist", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_streams_match_single_network() {
+        let mut model = EchoStateNetwork::new(
+            1,
+            1,
+            20,
+            0.2,
+            1.0,
+            0.9,
+            |x| x.tanh(),
+            Some(0.1),
+            None,
+            0.5,
+            |x| x.clone_owned(),
+            |x| x.clone_owned(),
+            false,
+            ReadoutConfig::default(),
+        )
+        .unwrap();
+        let mut streams = model.multi_stream().unwrap();
+        streams.add_stream(7).unwrap();
+        streams.add_stream(3).unwrap();
+
+        let sequences = [[0.1, 0.5, -0.3, 0.8], [1.0, -1.0, 0.2, 0.0]];
+        let outputs = sequences[0]
+            .iter()
+            .zip(sequences[1].iter())
+            .map(|(u0, u1)| streams.step_samples(&[vec![*u0], vec![*u1]]).unwrap())
+            .collect::<Vec<_>>();
+
+        for (n, u) in sequences[1].iter().enumerate() {
+            let y = model.estimate(&[*u]).unwrap();
+            assert_approx_eq!(y[0], outputs[n][1][0]);
+        }
+
+        streams.reset_stream(3).unwrap();
+        assert_eq!(streams.state(3).unwrap().norm(), 0.0);
+        assert!(streams.state(7).unwrap().norm() > 0.0);
+        streams.remove_stream(7).unwrap();
+        assert_eq!(streams.stream_ids(), &[3]);
+    }
+}
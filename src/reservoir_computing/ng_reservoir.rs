@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+
+use nalgebra as na;
+
+use crate::*;
+
+/// Next-generation reservoir computing, a nonlinear vector autoregression (NVAR).
+/// The features are a constant term, the current input and 'k' - 1 delayed copies of it,
+/// and the monomials of these linear features up to a polynomial 'order'.
+/// There is no random reservoir; only the readout is trained.
+pub struct NGReservoir<T = f64> {
+    n_u: u64,
+    n_y: u64,
+    k: usize,
+    stride: usize,
+    /// Index combinations of the linear features, one per nonlinear monomial
+    monomials: Vec<Vec<usize>>,
+    /// Last inputs, newest first
+    history: VecDeque<na::DVector<T>>,
+    output: Output<T>,
+    online_optimizer: Option<OnlineOptimizer<T>>,
+    offline_optimizer: Option<OfflineOptimizer<T>>,
+}
+
+impl NGReservoir {
+    /// Create a new NGReservoir.
+    /// n_u: The number of input variables.
+    /// n_y: The number of output variables.
+    /// k: The number of time-delayed copies of the input, including the current input.
+    /// stride: The number of steps between two delayed copies.
+    /// order: The maximum order of the polynomial monomials. 1 gives a linear autoregression.
+    pub fn new(
+        n_u: u64,
+        n_y: u64,
+        k: usize,
+        stride: usize,
+        order: usize,
+        readout: ReadoutConfig,
+    ) -> Result<Self> {
+        Self::new_with_precision(n_u, n_y, k, stride, order, readout)
+    }
+}
+
+impl<T: Float> NGReservoir<T> {
+    /// Create a new NGReservoir computing in the floating-point type 'T'.
+    /// The parameters are the same as `new`.
+    pub fn new_with_precision(
+        n_u: u64,
+        n_y: u64,
+        k: usize,
+        stride: usize,
+        order: usize,
+        readout: ReadoutConfig,
+    ) -> Result<Self> {
+        if k == 0 || stride == 0 || order == 0 {
+            return Err(EsnError::InvalidParameter(
+                "The number of delays, the stride and the order must be positive".to_string(),
+            ));
+        }
+
+        let n_linear = k * n_u as usize;
+        let monomials = (2..=order)
+            .flat_map(|degree| Self::combinations(n_linear, degree))
+            .collect::<Vec<_>>();
+        let n_features = (1 + n_linear + monomials.len()) as u64;
+
+        Ok(NGReservoir {
+            n_u,
+            n_y,
+            k,
+            stride,
+            monomials,
+            history: VecDeque::new(),
+            output: Output::new(n_y, n_features),
+            online_optimizer: Some(readout.online.build(n_features, n_y)?),
+            offline_optimizer: Some(readout.offline.build(n_features, n_y)?),
+        })
+    }
+
+    /// Return the non-decreasing index combinations of 'degree' indices out of 'n',
+    /// i.e. the unique monomials of this degree.
+    fn combinations(n: usize, degree: usize) -> Vec<Vec<usize>> {
+        let mut combinations = vec![];
+        let mut combination = vec![0; degree];
+        loop {
+            combinations.push(combination.clone());
+
+            // Find the last index which can still be increased.
+            let Some(position) = combination.iter().rposition(|&i| i + 1 < n) else {
+                return combinations;
+            };
+            let next = combination[position] + 1;
+            combination[position..].fill(next);
+        }
+    }
+
+    pub fn readout_weight(&self) -> &na::DMatrix<T> {
+        self.output.output_weight()
+    }
+
+    /// Replace both optimizers with new ones built from the configuration.
+    pub fn set_readout_config(&mut self, readout: ReadoutConfig) -> Result<()> {
+        let n_features = self.n_features();
+        let online_optimizer = readout.online.build(n_features, self.n_y)?;
+        let offline_optimizer = readout.offline.build(n_features, self.n_y)?;
+        self.online_optimizer = Some(online_optimizer);
+        self.offline_optimizer = Some(offline_optimizer);
+        Ok(())
+    }
+
+    /// Return the optimizer used by the offline training and its accumulated data.
+    pub fn offline_optimizer(&self) -> Option<&OfflineOptimizer<T>> {
+        self.offline_optimizer.as_ref()
+    }
+
+    /// Add an input to the history and return the features of the readout.
+    /// The delayed copies before the start of the sequence are zero.
+    fn call(&mut self, u: na::DVector<T>) -> na::DVector<T> {
+        self.history.push_front(u);
+        self.history.truncate((self.k - 1) * self.stride + 1);
+
+        let n_u = self.n_u as usize;
+        let mut linear = na::DVector::zeros(self.k * n_u);
+        for (delay, u) in self.history.iter().step_by(self.stride).enumerate() {
+            linear.rows_mut(delay * n_u, n_u).copy_from(u);
+        }
+
+        let mut features = na::DVector::zeros(self.n_features() as usize);
+        features[0] = T::one();
+        features.rows_mut(1, linear.len()).copy_from(&linear);
+        for (n, monomial) in self.monomials.iter().enumerate() {
+            features[1 + linear.len() + n] = monomial.iter().fold(T::one(), |p, &i| p * linear[i]);
+        }
+        features
+    }
+}
+
+impl<T: Float> ReservoirComputing<T> for NGReservoir<T> {
+    /// Online training method.
+    /// teaching_input: The features of the readout, e.g. harvested by `harvest_states`.
+    fn train(&mut self, teaching_input: &[T], teaching_output: &[T]) -> Result<()> {
+        check_sample(0, teaching_input, self.n_features() as usize)?;
+        check_sample(0, teaching_output, self.n_y as usize)?;
+        let optimizer = self
+            .online_optimizer
+            .as_mut()
+            .ok_or(EsnError::NotInitialized("online optimizer"))?;
+        let x = na::DVector::from_vec(teaching_input.to_vec());
+        let d = na::DVector::from_vec(teaching_output.to_vec());
+        optimizer.set_data(&x, &d)?;
+        self.output.set_weight(optimizer.fit()?);
+        self.output.set_link(optimizer.link());
+
+        Ok(())
+    }
+
+    fn estimate(&mut self, input: &[T]) -> Result<Vec<T>> {
+        check_sample(0, input, self.n_u as usize)?;
+        let features = self.call(na::DVector::from_column_slice(input));
+        Ok(self.output.call(&features).as_slice().to_vec())
+    }
+
+    /// The states are the NVAR features of each step.
+    fn harvest_states(
+        &mut self,
+        teaching_input: &[Vec<T>],
+        teaching_output: &[Vec<T>],
+    ) -> Result<(na::DMatrix<T>, na::DMatrix<T>)> {
+        check_dimension(teaching_input.len(), teaching_output.len())?;
+        check_samples(teaching_input, self.n_u as usize)?;
+        check_samples(teaching_output, self.n_y as usize)?;
+
+        let mut states = na::DMatrix::zeros(self.n_features() as usize, teaching_input.len());
+        for (n, input) in teaching_input.iter().enumerate() {
+            let features = self.call(na::DVector::from_column_slice(input));
+            states.set_column(n, &features);
+        }
+        let targets = na::DMatrix::from_fn(self.n_y as usize, teaching_output.len(), |i, n| {
+            teaching_output[n][i]
+        });
+
+        Ok((states, targets))
+    }
+
+    fn fit_from_states(&mut self, states: &na::DMatrix<T>, targets: &na::DMatrix<T>) -> Result<()> {
+        let optimizer = self
+            .offline_optimizer
+            .as_mut()
+            .ok_or(EsnError::NotInitialized("offline optimizer"))?;
+        optimizer.set_batch(states, targets)?;
+        self.output.set_weight(optimizer.fit()?);
+        self.output.set_link(optimizer.link());
+
+        Ok(())
+    }
+
+    /// Clear the delayed inputs.
+    fn reset(&mut self) {
+        self.history.clear();
+    }
+
+    fn n_inputs(&self) -> u64 {
+        self.n_u
+    }
+
+    fn n_outputs(&self) -> u64 {
+        self.n_y
+    }
+
+    fn n_features(&self) -> u64 {
+        (1 + self.k * self.n_u as usize + self.monomials.len()) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_features() {
+        let mut model = NGReservoir::new(1, 1, 2, 1, 2, ReadoutConfig::default()).unwrap();
+        assert_eq!(model.n_features(), 1 + 2 + 3);
+
+        model.call(na::DVector::from_vec(vec![2.0]));
+        let features = model.call(na::DVector::from_vec(vec![3.0]));
+        assert_eq!(features.as_slice(), &[1.0, 3.0, 2.0, 9.0, 6.0, 4.0]);
+    }
+
+    #[test]
+    fn test_learns_quadratic_autoregression() {
+        let inputs = (0..50)
+            .map(|n| vec![(0.3 * n as f64).sin()])
+            .collect::<Vec<_>>();
+        let outputs = (0..50)
+            .map(|n| {
+                let previous = if n > 0 { inputs[n - 1][0] } else { 0.0 };
+                vec![0.5 + inputs[n][0] * previous]
+            })
+            .collect::<Vec<_>>();
+
+        let mut model = NGReservoir::new(1, 1, 2, 1, 2, ReadoutConfig::ridge(1e-9)).unwrap();
+        model.offline_train(&inputs[..40], &outputs[..40]).unwrap();
+        for (input, output) in inputs[40..].iter().zip(outputs[40..].iter()) {
+            assert_approx_eq!(model.estimate(input).unwrap()[0], output[0], 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_rejects_zero_parameters() {
+        for (k, stride, order) in [(0, 1, 2), (2, 0, 2), (2, 1, 0)] {
+            let result = NGReservoir::new(1, 1, k, stride, order, ReadoutConfig::default());
+            assert!(matches!(result, Err(EsnError::InvalidParameter(_))));
+        }
+    }
+}
@@ -2,19 +2,17 @@ use nalgebra as na;
 
 use crate::*;
 
-pub struct PhysicalReservoir {
-    output: Output,
-    rls: Option<RLS>,
-    ridge: Option<Ridge>,
+pub struct PhysicalReservoir<T = f64> {
+    n_y: u64,
+    n_x: u64,
+    output: Output<T>,
+    online_optimizer: Option<OnlineOptimizer<T>>,
+    offline_optimizer: Option<OfflineOptimizer<T>>,
 }
 
 impl PhysicalReservoir {
-    pub fn new(n_y: u64, n_x: u64) -> Self {
-        PhysicalReservoir {
-            output: Output::new(n_y, n_x),
-            rls: Some(RLS::new(n_x, n_y, 1.0, 1.0)),
-            ridge: Some(Ridge::new(n_x, n_y, 0.1)),
-        }
+    pub fn new(n_y: u64, n_x: u64) -> Result<Self> {
+        Self::new_with_config(n_y, n_x, ReadoutConfig::default())
     }
 
     /// Create a new PhysicalReservoir with parameters.
@@ -22,56 +20,199 @@ impl PhysicalReservoir {
     /// n_x: The number of input(sensor) nodes.
     /// rls_param: Parameters for RLS. (forgetting_factor, regularization_parameter)
     /// ridge_param: Parameter for Ridge regression. Regularization parameter.
-    pub fn new_with_param(n_y: u64, n_x: u64, rls_param: (f64, f64), ridge_param: f64) -> Self {
-        PhysicalReservoir {
+    pub fn new_with_param(
+        n_y: u64,
+        n_x: u64,
+        rls_param: (f64, f64),
+        ridge_param: f64,
+    ) -> Result<Self> {
+        let readout = ReadoutConfig {
+            online: OnlineOptimizerKind::RLS {
+                lambda: rls_param.0,
+                delta: rls_param.1,
+            },
+            offline: OfflineOptimizerKind::Ridge { beta: ridge_param },
+        };
+        Self::new_with_config(n_y, n_x, readout)
+    }
+
+    /// Create a new PhysicalReservoir with a readout training configuration.
+    /// n_y: The number of output nodes.
+    /// n_x: The number of input(sensor) nodes.
+    pub fn new_with_config(n_y: u64, n_x: u64, readout: ReadoutConfig) -> Result<Self> {
+        Self::new_with_precision(n_y, n_x, readout)
+    }
+}
+
+impl<T: Float> PhysicalReservoir<T> {
+    /// Create a new PhysicalReservoir computing in the floating-point type 'T',
+    /// e.g. `PhysicalReservoir::<f32>::new_with_precision(n_y, n_x, ReadoutConfig::default()).unwrap()`.
+    pub fn new_with_precision(n_y: u64, n_x: u64, readout: ReadoutConfig) -> Result<Self> {
+        Ok(PhysicalReservoir {
+            n_y,
+            n_x,
             output: Output::new(n_y, n_x),
-            rls: Some(RLS::new(n_x, n_y, rls_param.0, rls_param.1)),
-            ridge: Some(Ridge::new(n_x, n_y, ridge_param)),
-        }
+            online_optimizer: Some(readout.online.build(n_x, n_y)?),
+            offline_optimizer: Some(readout.offline.build(n_x, n_y)?),
+        })
     }
 
-    pub fn readout_weight(&self) -> &na::DMatrix<f64> {
+    pub fn readout_weight(&self) -> &na::DMatrix<T> {
         self.output.output_weight()
     }
-}
 
-impl ReservoirComputing for PhysicalReservoir {
-    /// Online training method.
-    /// teaching_input: Input data for training. In this case, it is a sensor data from the physical reservoir.
-    fn train(&mut self, teaching_input: &[f64], teaching_output: &[f64]) {
-        if self.rls.is_none() {
-            panic!("RLS is not initialized");
+    /// Convert the trained readout to another floating-point type, e.g. to run inference in f32.
+    /// The optimizers are converted with their accumulated data, so the training can be resumed.
+    pub fn to_precision<U: Float>(&self) -> PhysicalReservoir<U> {
+        PhysicalReservoir {
+            n_y: self.n_y,
+            n_x: self.n_x,
+            output: self.output.to_precision(),
+            online_optimizer: self.online_optimizer.as_ref().map(|o| o.to_precision()),
+            offline_optimizer: self.offline_optimizer.as_ref().map(|o| o.to_precision()),
         }
-        let x = na::DVector::from_vec(teaching_input.to_vec());
-        let d = na::DVector::from_vec(teaching_output.to_vec());
-        let rls = self.rls.as_mut().unwrap();
-        rls.set_data(&x, &d);
-        let weight = rls.fit();
-        self.output.set_weight(weight);
     }
 
-    fn offline_train(&mut self, teaching_input: &[Vec<f64>], teaching_output: &[Vec<f64>]) {
-        if self.ridge.is_none() {
-            panic!("Ridge is not initialized");
+    /// Replace both optimizers with new ones built from the configuration.
+    pub fn set_readout_config(&mut self, readout: ReadoutConfig) -> Result<()> {
+        let online_optimizer = readout.online.build(self.n_x, self.n_y)?;
+        let offline_optimizer = readout.offline.build(self.n_x, self.n_y)?;
+        self.online_optimizer = Some(online_optimizer);
+        self.offline_optimizer = Some(offline_optimizer);
+        Ok(())
+    }
+
+    /// Start the online optimizer from the current readout weight,
+    /// e.g. to fine-tune online a readout trained offline.
+    pub fn warm_start_online_optimizer(&mut self) -> Result<()> {
+        match self.online_optimizer.as_mut() {
+            Some(optimizer) => optimizer.set_weight(self.output.output_weight().clone()),
+            None => Err(EsnError::NotInitialized("online optimizer")),
+        }
+    }
+
+    /// Return the optimizer used by the offline training and its accumulated data.
+    pub fn offline_optimizer(&self) -> Option<&OfflineOptimizer<T>> {
+        self.offline_optimizer.as_ref()
+    }
+
+    /// Save the offline optimizer with its accumulated data, e.g. after `offline_train`.
+    pub fn save_offline_optimizer(&self, path: &str) -> Result<()> {
+        match self.offline_optimizer.as_ref() {
+            Some(optimizer) => write_offline_optimizer(optimizer, path),
+            None => Err(EsnError::NotInitialized("offline optimizer")),
         }
+    }
 
-        let ridge = self.ridge.as_mut().unwrap();
-        for (input, output) in teaching_input.iter().zip(teaching_output.iter()) {
-            let x = na::DVector::from_vec(input.clone());
-            let d = na::DVector::from_vec(output.clone());
-            ridge.set_data(&x, &d);
+    /// Load an offline optimizer saved by `save_offline_optimizer`.
+    /// The following `offline_train` adds the new data to the loaded one before fitting.
+    pub fn load_offline_optimizer(&mut self, path: &str) -> Result<()> {
+        let optimizer = read_offline_optimizer(path)?;
+        let (n_x, n_y) = optimizer.shape();
+        if (n_x, n_y) != (self.n_x, self.n_y) {
+            return Err(EsnError::ShapeMismatch {
+                expected: (self.n_y as usize, self.n_x as usize),
+                actual: (n_y as usize, n_x as usize),
+            });
         }
+        self.offline_optimizer = Some(optimizer);
+        Ok(())
+    }
+
+    /// Replace the optimizer used by the online training.
+    /// e.g. an `LMS` when RLS is too expensive for the reservoir size.
+    pub fn set_online_optimizer(&mut self, optimizer: impl Into<OnlineOptimizer<T>>) {
+        self.online_optimizer = Some(optimizer.into());
+    }
+
+    /// Replace the optimizer used by the offline training.
+    /// e.g. an `ElasticNet` to obtain a sparse readout weight.
+    pub fn set_offline_optimizer(&mut self, optimizer: impl Into<OfflineOptimizer<T>>) {
+        self.offline_optimizer = Some(optimizer.into());
+    }
+}
 
-        let weight = ridge.fit();
+impl<T: Float> ReservoirComputing<T> for PhysicalReservoir<T> {
+    /// Online training method.
+    /// teaching_input: Input data for training. In this case, it is a sensor data from the physical reservoir.
+    fn train(&mut self, teaching_input: &[T], teaching_output: &[T]) -> Result<()> {
+        check_sample(0, teaching_input, self.n_x as usize)?;
+        check_sample(0, teaching_output, self.n_y as usize)?;
+        let optimizer = self
+            .online_optimizer
+            .as_mut()
+            .ok_or(EsnError::NotInitialized("online optimizer"))?;
+        let x = na::DVector::from_vec(teaching_input.to_vec());
+        let d = na::DVector::from_vec(teaching_output.to_vec());
+        optimizer.set_data(&x, &d)?;
+        let weight = optimizer.fit()?;
         self.output.set_weight(weight);
+        self.output.set_link(optimizer.link());
+
+        Ok(())
     }
 
     /// Estimate method.
     /// input: Input data for estimating. In this case, it is a sensor data from the physical reservoir.
-    fn estimate(&mut self, input: &[f64]) -> Vec<f64> {
+    fn estimate(&mut self, input: &[T]) -> Result<Vec<T>> {
+        check_sample(0, input, self.n_x as usize)?;
         let x = na::DVector::from_vec(input.to_vec());
         let output = self.output.call(&x);
-        output.data.as_slice().to_vec()
+        Ok(output.data.as_slice().to_vec())
+    }
+
+    /// Without a state in the model, the whole sequence is estimated by a single matrix product.
+    fn estimate_sequence(&mut self, inputs: &na::DMatrix<T>) -> Result<na::DMatrix<T>> {
+        check_dimension(self.n_x as usize, inputs.nrows())?;
+        Ok(self.output.call_batch(inputs))
+    }
+
+    /// The states of a physical reservoir are the sensor data themselves.
+    fn harvest_states(
+        &mut self,
+        teaching_input: &[Vec<T>],
+        teaching_output: &[Vec<T>],
+    ) -> Result<(na::DMatrix<T>, na::DMatrix<T>)> {
+        check_dimension(teaching_input.len(), teaching_output.len())?;
+        check_samples(teaching_input, self.n_x as usize)?;
+        check_samples(teaching_output, self.n_y as usize)?;
+
+        let x = na::DMatrix::from_fn(self.n_x as usize, teaching_input.len(), |i, n| {
+            teaching_input[n][i]
+        });
+        let d = na::DMatrix::from_fn(self.n_y as usize, teaching_output.len(), |i, n| {
+            teaching_output[n][i]
+        });
+
+        Ok((x, d))
+    }
+
+    fn fit_from_states(&mut self, states: &na::DMatrix<T>, targets: &na::DMatrix<T>) -> Result<()> {
+        let optimizer = self
+            .offline_optimizer
+            .as_mut()
+            .ok_or(EsnError::NotInitialized("offline optimizer"))?;
+        optimizer.set_batch(states, targets)?;
+        let weight = optimizer.fit()?;
+        self.output.set_weight(weight);
+        self.output.set_link(optimizer.link());
+
+        Ok(())
+    }
+
+    /// A physical reservoir has no internal state in the model.
+    fn reset(&mut self) {}
+
+    fn n_inputs(&self) -> u64 {
+        self.n_x
+    }
+
+    fn n_outputs(&self) -> u64 {
+        self.n_y
+    }
+
+    fn n_features(&self) -> u64 {
+        self.n_x
     }
 }
 
@@ -82,15 +223,149 @@ mod tests {
 
     #[test]
     fn test_physical_reservoir() {
-        let mut reservoir = PhysicalReservoir::new_with_param(1, 2, (0.0, 0.0), 0.0);
+        let mut reservoir = PhysicalReservoir::new_with_param(1, 2, (0.0, 0.0), 0.0).unwrap();
         let teaching_input = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
         let teaching_output = vec![vec![1.5], vec![3.5]];
 
-        reservoir.offline_train(&teaching_input, &teaching_output);
+        reservoir
+            .offline_train(&teaching_input, &teaching_output)
+            .unwrap();
 
         let input = vec![5.0, 6.0];
-        let output = reservoir.estimate(&input);
+        let output = reservoir.estimate(&input).unwrap();
         let expected_output = 5.5;
         assert_approx_eq!(output[0], expected_output);
     }
+
+    #[test]
+    fn test_single_precision() {
+        let readout = ReadoutConfig {
+            offline: OfflineOptimizerKind::Ridge { beta: 1e-6 },
+            ..Default::default()
+        };
+        let mut reservoir = PhysicalReservoir::<f32>::new_with_precision(1, 2, readout).unwrap();
+        let teaching_input = vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![0.0, 1.0]];
+        let teaching_output = vec![vec![1.5], vec![3.5], vec![0.5]];
+
+        reservoir
+            .offline_train(&teaching_input, &teaching_output)
+            .unwrap();
+        let output: Vec<f32> = reservoir.estimate(&[5.0, 6.0]).unwrap();
+        assert_approx_eq!(output[0], 5.5, 1e-3);
+
+        let converted = reservoir.to_precision::<f64>();
+        for (e, a) in reservoir
+            .readout_weight()
+            .iter()
+            .zip(converted.readout_weight().iter())
+        {
+            assert_approx_eq!(*e as f64, a);
+        }
+    }
+
+    #[test]
+    fn test_to_precision_keeps_optimizers() {
+        let readout = ReadoutConfig {
+            online: OnlineOptimizerKind::LMS {
+                step_size: 0.01,
+                momentum: 0.0,
+                normalized: true,
+            },
+            offline: OfflineOptimizerKind::ElasticNet {
+                alpha: 0.1,
+                l1_ratio: 0.5,
+            },
+        };
+        let mut reservoir = PhysicalReservoir::new_with_config(1, 2, readout).unwrap();
+        reservoir
+            .offline_train(&[vec![1.0, 2.0], vec![3.0, 4.0]], &[vec![1.5], vec![3.5]])
+            .unwrap();
+
+        let converted = reservoir.to_precision::<f32>();
+        assert_eq!(converted.offline_optimizer().unwrap().n_samples(), 2);
+        assert!(matches!(
+            converted.online_optimizer,
+            Some(OnlineOptimizer::LMS(_))
+        ));
+        assert!(matches!(
+            converted.offline_optimizer,
+            Some(OfflineOptimizer::ElasticNet(_))
+        ));
+    }
+
+    #[test]
+    fn test_boxed_reservoir_computing() {
+        let mut model: Box<dyn ReservoirComputing> =
+            Box::new(PhysicalReservoir::new_with_param(1, 2, (1.0, 1.0), 0.0).unwrap());
+        let teaching_input = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+        let teaching_output = vec![vec![1.5], vec![3.5]];
+
+        let (states, targets) = model
+            .harvest_states(&teaching_input, &teaching_output)
+            .unwrap();
+        model.fit_from_states(&states, &targets).unwrap();
+        model.reset();
+
+        assert_eq!((model.n_inputs(), model.n_outputs()), (2, 1));
+        let outputs = model
+            .estimate_batch(&[vec![5.0, 6.0], vec![7.0, 8.0]])
+            .unwrap();
+        assert_eq!(outputs.shape(), (1, 2));
+        assert_approx_eq!(outputs[(0, 0)], 5.5);
+        assert_approx_eq!(outputs[(0, 1)], 7.5);
+    }
+
+    #[test]
+    fn test_estimate_sequence_matches_estimate() {
+        let mut reservoir = PhysicalReservoir::new(2, 3).unwrap();
+        let inputs = na::DMatrix::from_fn(3, 4, |i, n| (i + 2 * n) as f64);
+
+        let outputs = reservoir.estimate_sequence(&inputs).unwrap();
+        for (n, input) in inputs.column_iter().enumerate() {
+            let output = reservoir.estimate(input.clone_owned().as_slice()).unwrap();
+            for (e, a) in output.iter().zip(outputs.column(n).iter()) {
+                assert_approx_eq!(e, a);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample_with_wrong_length() {
+        let mut reservoir = PhysicalReservoir::new(1, 2).unwrap();
+        let teaching_input = vec![vec![1.0, 2.0], vec![3.0], vec![5.0, 6.0]];
+        let teaching_output = vec![vec![1.5], vec![3.5], vec![5.5]];
+
+        let result = reservoir.offline_train(&teaching_input, &teaching_output);
+        assert!(matches!(
+            result,
+            Err(EsnError::SampleDimensionMismatch {
+                index: 1,
+                expected: 2,
+                actual: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_resume_offline_training() {
+        let path = std::env::temp_dir().join("esn_test_resume_offline_training.json");
+        let path = path.to_str().unwrap();
+
+        let mut reservoir = PhysicalReservoir::new_with_param(1, 2, (1.0, 1.0), 1e-9).unwrap();
+        reservoir
+            .offline_train(&[vec![1.0, 2.0]], &[vec![1.5]])
+            .unwrap();
+        reservoir.save_offline_optimizer(path).unwrap();
+
+        let mut resumed = PhysicalReservoir::new_with_param(1, 2, (1.0, 1.0), 1e-9).unwrap();
+        resumed.load_offline_optimizer(path).unwrap();
+        resumed
+            .offline_train(&[vec![3.0, 4.0]], &[vec![3.5]])
+            .unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(resumed.offline_optimizer().unwrap().n_samples(), 2);
+        let output = resumed.estimate(&[5.0, 6.0]).unwrap();
+        assert_approx_eq!(output[0], 5.5);
+    }
 }
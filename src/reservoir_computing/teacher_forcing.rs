@@ -0,0 +1,102 @@
+use nalgebra as na;
+use rand::prelude::*;
+use rand_distr::Normal;
+use serde::{Deserialize, Serialize};
+
+use crate::{EsnError, Float, Result, cast};
+
+/// How the output fed back to the reservoir is chosen while harvesting the states of a teaching sequence.
+/// The fed-back signal is always in the output space, like the one fed back by `estimate`.
+/// The default is pure teacher forcing: the teaching output of the previous step is fed back as it is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TeacherForcing {
+    /// Standard deviation of the Gaussian noise added to the fed-back teaching output.
+    pub noise_level: f64,
+    /// Scheduled sampling: probability in [0, 1] of feeding back the prediction of the model
+    /// instead of the teaching output, increased linearly from 0 at the start of the sequence to this value at the end.
+    /// The prediction uses the current readout, so the sampling only starts once a readout has been fitted;
+    /// a one-shot `offline_train` of a new model uses pure teacher forcing, and the sampling helps repeated
+    /// or incremental fits.
+    pub sampling_probability: f64,
+}
+
+impl TeacherForcing {
+    /// Teacher forcing with Gaussian noise of standard deviation 'noise_level' on the teaching output.
+    pub fn noisy(noise_level: f64) -> Self {
+        TeacherForcing {
+            noise_level,
+            sampling_probability: 0.0,
+        }
+    }
+
+    /// Scheduled sampling up to 'sampling_probability' at the end of the sequence.
+    pub fn scheduled_sampling(sampling_probability: f64) -> Self {
+        TeacherForcing {
+            noise_level: 0.0,
+            sampling_probability,
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        if !(0.0..).contains(&self.noise_level) {
+            return Err(EsnError::InvalidParameter(
+                "The teacher noise level must be non-negative".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.sampling_probability) {
+            return Err(EsnError::InvalidParameter(
+                "The sampling probability must be in [0, 1]".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Return the output fed back after step 'n' of a sequence of 'length' steps.
+    /// 'predict' is only called when the prediction of the model is sampled.
+    pub(crate) fn feedback<T: Float>(
+        &self,
+        teacher: na::DVector<T>,
+        predict: impl FnOnce() -> na::DVector<T>,
+        n: usize,
+        length: usize,
+    ) -> na::DVector<T> {
+        let mut rng = thread_rng();
+
+        let probability = self.sampling_probability * (n + 1) as f64 / length as f64;
+        if probability > 0.0 && rng.gen_bool(probability.min(1.0)) {
+            return predict();
+        }
+
+        if self.noise_level > 0.0 {
+            let normal = Normal::new(0.0, self.noise_level).unwrap();
+            teacher.map(|y| y + cast::<T>(normal.sample(&mut rng)))
+        } else {
+            teacher
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_sampling_at_the_end() {
+        let teacher_forcing = TeacherForcing::scheduled_sampling(1.0);
+        let teacher = na::DVector::from_vec(vec![1.0]);
+        let predicted = na::DVector::from_vec(vec![2.0]);
+
+        let fed_back = teacher_forcing.feedback(teacher.clone(), || predicted.clone(), 9, 10);
+        assert_eq!(fed_back, predicted);
+
+        let fed_back = TeacherForcing::default().feedback(teacher.clone(), || predicted, 9, 10);
+        assert_eq!(fed_back, teacher);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert!(TeacherForcing::noisy(-1.0).validate().is_err());
+        assert!(TeacherForcing::scheduled_sampling(1.5).validate().is_err());
+        assert!(TeacherForcing::noisy(0.1).validate().is_ok());
+    }
+}
@@ -3,16 +3,16 @@ use crate::*;
 /// Serialize the output of the echo state network.
 /// To save the log of the echo state network, optimizer, and input/output data as a JSON file.
 #[allow(clippy::too_many_arguments)]
-pub fn write_as_serde(
-    model: EchoStateNetwork,
-    train_input: &Vec<Vec<f64>>,
-    train_expected_output: &Vec<Vec<f64>>,
-    test_input: &Vec<Vec<f64>>,
-    test_expected_output: &Vec<Vec<f64>>,
-    test_estimated_output: Vec<Vec<f64>>,
+pub fn write_as_serde<T: Float>(
+    model: EchoStateNetwork<T>,
+    train_input: &Vec<Vec<T>>,
+    train_expected_output: &Vec<Vec<T>>,
+    test_input: &Vec<Vec<T>>,
+    test_expected_output: &Vec<Vec<T>>,
+    test_estimated_output: Vec<Vec<T>>,
     path: Option<&str>,
-) {
-    let model_json = model.serde_json().unwrap();
+) -> Result<()> {
+    let model_json = model.serde_json()?;
     let train_input_log = format!("{:?}", train_input);
     let train_expected_output_log = format!("{:?}", train_expected_output);
     let test_input_log = format!("{:?}", test_input);
@@ -35,6 +35,25 @@ pub fn write_as_serde(
         Some(p) => format!("{}/{}", p, name),
         None => format!("./log/{}", name),
     };
-    let _file = std::fs::File::create(&path).unwrap();
-    std::fs::write(&path, output).unwrap();
+    std::fs::write(&path, output)?;
+
+    Ok(())
+}
+
+/// Save the accumulated training data of an offline optimizer as a JSON file,
+/// so that the training can be resumed with new data later.
+pub fn write_offline_optimizer<T: Float>(
+    optimizer: &OfflineOptimizer<T>,
+    path: &str,
+) -> Result<()> {
+    let json = serde_json::to_string(optimizer)?;
+    std::fs::write(path, json)?;
+
+    Ok(())
+}
+
+/// Load an offline optimizer saved by `write_offline_optimizer`.
+pub fn read_offline_optimizer<T: Float>(path: &str) -> Result<OfflineOptimizer<T>> {
+    let json = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
 }